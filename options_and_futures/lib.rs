@@ -2,14 +2,84 @@
 
 #[ink::contract]
 mod options_and_futures {
-    use ink::{prelude::vec::Vec, storage::traits::StorageLayout};
-    use ink::storage::Mapping;
-
-    use scale::{Encode, Decode};
+    use ink::prelude::{boxed::Box, vec::Vec};
+    use ink::storage::{Lazy, Mapping};
 
     type Reputation = i128;
 
-    #[derive(scale::Decode, scale::Encode)]
+    /// Whose reputation history a checkpoint belongs to: a voter's, or the
+    /// total's for `None`.
+    type HistoryKey = Option<AccountId>;
+
+    /// Where a ranking link starts: at a voter, or at the head for `None`.
+    type RankFrom = Option<AccountId>;
+
+    /// A link of the entropy hash chain: the block it was last extended in
+    /// and its value.
+    type EntropyLink = (BlockNumber, [u8; 32]);
+
+    /// Identifies a reputation category registered with `add_category`.
+    type CategoryId = u32;
+
+    /// Permissions that can be granted to accounts. The owner and `Admin`
+    /// holders implicitly hold every role.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Role {
+        /// Grants and revokes roles and changes contract parameters.
+        Admin,
+        /// Registers voters.
+        Registrar,
+        /// Removes voters.
+        Moderator,
+        /// Pauses the contract.
+        Pauser,
+    }
+
+    /// Groups of state-changing messages that can be paused on their own.
+    /// Configuration, role and ownership messages are never paused so an
+    /// incident can still be handled.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Operation {
+        /// `add_voter`, `add_voters`, `register`, `request_membership` and
+        /// `vouch`.
+        Registration,
+        /// `remove_voter`, `remove_voters`, `deregister`, `seize_deposit`,
+        /// `slash`, `appeal` and `reverse_slash`.
+        Removal,
+        /// `vote`, `vote_many`, `retract_vote`, `reallocate`, `commit` and
+        /// `reveal`.
+        Voting,
+        /// `delegate` and `revoke_delegation`.
+        Delegation,
+        /// `propose`, `cast_ballot` and `execute`.
+        Governance,
+        /// `open_dispute`, `draw_jurors`, `rule` and `resolve_dispute`.
+        Disputes,
+    }
+
+    /// Upper bound on the number of voters returned by a single
+    /// `get_voters_page` call.
+    pub const MAX_PAGE_SIZE: u32 = 100;
+
+    /// Upper bound on the jurors selected for one dispute.
+    pub const MAX_JURORS: u32 = 25;
+
+    /// Blocks between opening a dispute and drawing its jurors, so the
+    /// draw depends on activity the disputant could not foresee.
+    pub const JUROR_DRAW_DELAY: BlockNumber = 3;
+
+    /// Upper bound on the number of reputation categories.
+    pub const MAX_CATEGORIES: u32 = 32;
+
+    #[derive(Debug, PartialEq, Eq, scale::Decode, scale::Encode)]
     #[cfg_attr(
         feature = "std",
         derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
@@ -17,11 +87,125 @@ mod options_and_futures {
     pub enum Error {
         OnlyOwnerFunction,
         UnregisteredVoter,
+        /// No longer returned; `InsufficientVotes` replaced it.
         VoterAlreadyVoted,
         VoterEqualToCandidate,
+        VoterAlreadyRegistered,
+        RetractExceedsVotes,
+        SelfDelegation,
+        DelegationExists,
+        NoDelegation,
+        DelegationCycle,
+        DelegationTooDeep,
+        MissingRole(Role),
+        NotPendingOwner,
+        NoPendingOwnership,
+        RegistrationClosed,
+        WrongDeposit,
+        NoDeposit,
+        CooldownActive,
+        TransferFailed,
+        BatchTooLarge,
+        /// The batch entry at this index failed with the inner error.
+        BatchEntryFailed(u32, Box<Error>),
+        /// The voter cannot cover the cost from their own and delegated
+        /// votes.
+        InsufficientVotes,
+        /// A vote, retraction or delegation of zero votes.
+        ZeroVotes,
+        /// The candidate's reputation would leave the `Reputation` range.
+        ReputationOverflow,
+        /// A vote balance or ledger total would leave the `u128` or `i128`
+        /// range.
+        VotesOverflow,
+        /// A configuration value is out of range.
+        InvalidConfig,
+        /// The vote would exceed the per-candidate cap for this epoch.
+        CandidateEpochCapExceeded,
+        /// The vote would give one candidate more than the allowed share of
+        /// the voter's budget for this epoch.
+        CandidateShareExceeded,
+        /// The candidate recently voted for the caller and reciprocal votes
+        /// are blocked.
+        ReciprocalVoteBlocked,
+        VouchingClosed,
+        MembershipAlreadyRequested,
+        NoMembershipRequest,
+        AlreadyVouched,
+        /// The voucher's reputation is below the vouching threshold.
+        ReputationTooLow,
+        /// The operation, or the whole contract, is paused.
+        OperationPaused(Operation),
+        NotPaused,
+        /// The runtime rejected the new code hash.
+        UpgradeFailed,
+        /// Historical queries only accept blocks before the current one.
+        BlockNotPast,
+        GovernanceClosed,
+        UnknownProposal,
+        /// The proposal's voting period has not started or is over.
+        VotingNotOpen,
+        AlreadyVoted,
+        /// The voter had no positive reputation at the proposal's snapshot.
+        NoVotingWeight,
+        VotingNotEnded,
+        ProposalDefeated,
+        AlreadyExecuted,
+        /// A slash must take some reputation or votes, and never a negative
+        /// amount of reputation.
+        EmptySlash,
+        UnknownSlash,
+        AppealWindowClosed,
+        SlashAlreadyReversed,
+        DisputesClosed,
+        UnknownVote,
+        /// Voters cannot dispute their own votes.
+        OwnVote,
+        VoteAlreadyDisputed,
+        /// Too few registered voters are unrelated to the dispute.
+        NotEnoughJurors,
+        UnknownDispute,
+        NotAJuror,
+        AlreadyRuled,
+        /// The dispute's ruling deadline has passed.
+        RulingClosed,
+        /// The dispute has neither a majority nor a passed deadline yet.
+        RulingOpen,
+        DisputeResolved,
+        /// Public voting is closed while a commit-reveal round runs.
+        RoundInProgress,
+        NotCommitPhase,
+        NotRevealPhase,
+        AlreadyCommitted,
+        NoCommitment,
+        /// The revealed vote does not match the commitment.
+        InvalidReveal,
+        /// No category with the given id has been added.
+        UnknownCategory,
+        /// `MAX_CATEGORIES` categories have been added already.
+        TooManyCategories,
+        /// Totals before `migrate` finished leave out version 1 voters.
+        MigrationPending,
+        /// The encoded call is not to a setter proposals can make, or its
+        /// arguments do not decode.
+        UnsupportedCall,
+        AlreadyAppealed,
+        /// The revealed vote costs more than the votes locked with it.
+        ExceedsLockedVotes,
+        /// Seized deposits need a treasury to go to.
+        NoTreasury,
+        /// The account was removed by a moderator; its deposit can only be
+        /// seized.
+        DepositFrozen,
+        /// The account still has a deposit from an earlier registration.
+        DepositPending,
+        /// Jurors can only be drawn `JUROR_DRAW_DELAY` blocks after the
+        /// dispute was opened.
+        DrawNotDue,
+        JurorsAlreadyDrawn,
     }
 
-    #[derive(scale::Decode, scale::Encode)]
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
     #[cfg_attr(
         feature = "std",
         derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
@@ -29,191 +213,6565 @@ mod options_and_futures {
     pub struct Voter {
         reputation: Reputation,
         address: AccountId,
-        available_votes: u128
+        available_votes: u128,
+        /// Block the voter registered at. `None` for voters registered
+        /// before storage version 2.
+        registered_at: Option<BlockNumber>,
     }
 
-    /// Defines the storage of your contract.
-    /// Add new fields to the below struct in order
-    /// to add new static storage fields to your contract.
-    #[ink(storage)]
-    pub struct OptionsAndFutures {
-        /// Stores a single `bool` value on the storage.
-        voters: ink::storage::Mapping<AccountId, Voter>,
-        voters_addresses: Vec<AccountId>,
-        owner: AccountId,
+    /// `Voter` as laid out by storage version 1.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    struct VoterV1 {
+        reputation: Reputation,
+        address: AccountId,
+        available_votes: u128,
     }
 
-    impl OptionsAndFutures {
-        /// Constructor that initializes the `bool` value to the given `init_value`.
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            let voters = Mapping::default();
-            let voters_addresses: Vec<AccountId> = Vec::new();
-            let owner = Self::env().caller();
-
-            Self {
-                voters,
-                voters_addresses,
-                owner
+    impl From<VoterV1> for Voter {
+        fn from(voter: VoterV1) -> Self {
+            Voter {
+                reputation: voter.reputation,
+                address: voter.address,
+                available_votes: voter.available_votes,
+                registered_at: None,
             }
         }
+    }
 
-        /// A message that can be called on instantiated contracts.
-        /// This one flips the value of the stored `bool` from `true`
-        /// to `false` and vice versa.
-        #[ink(message)]
-        pub fn add_voter(&mut self, voter: AccountId, available_votes: u128) -> Result<(), Error> {
-            
-            if self.env().caller() != self.owner {
-                return Err(Error::OnlyOwnerFunction)
+    /// Running totals of the votes one voter has cast on one candidate.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VoteRecord {
+        /// Sum of the signed votes cast and not retracted.
+        net: i128,
+        /// Sum of the absolute votes cast, less those retracted.
+        total_cast: u128,
+        /// Change the votes still make to the candidate's reputation, after
+        /// collusion rules and vote weighting.
+        effect: Reputation,
+        /// Decay clock when `effect` was last written, which it decays from
+        /// like reputation does.
+        decay_clock: u128,
+        /// First block of the epoch of the most recent vote, or zero without
+        /// epochs.
+        epoch: BlockNumber,
+        /// Absolute votes cast in `epoch` and not retracted since. Only these
+        /// are refunded when retracted, since refills have restored the
+        /// allowance spent on older ones.
+        refundable: u128,
+        /// Times the voter and the candidate had been removed when the entry
+        /// was written. It lapses once either is removed again.
+        removals: (u32, u32),
+        /// Block of the most recent vote.
+        last_block: BlockNumber,
+        /// Votes each delegation paid towards the `refundable` votes, which
+        /// go back to it before the voter is refunded anything.
+        delegated: Vec<(AccountId, u128)>,
+        /// Part of `effect` credited to each category the votes were cast
+        /// in, decaying along with it.
+        categories: Vec<(CategoryId, Reputation)>,
+    }
+
+    /// How many `available_votes` a vote costs.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum CostModel {
+        /// Each vote costs one credit.
+        #[default]
+        Linear,
+        /// Having cast `n` votes in total on a candidate costs `n²` credits,
+        /// so each further vote on the same candidate is more expensive.
+        Quadratic,
+    }
+
+    impl CostModel {
+        /// Credits charged for casting `votes` more votes on a candidate that
+        /// has already received `already_cast` votes from the same voter.
+        fn cost(&self, already_cast: u128, votes: u128) -> Result<u128, Error> {
+            match self {
+                CostModel::Linear => Some(votes),
+                // (m + n)² - m² = n * (2m + n)
+                CostModel::Quadratic => already_cast
+                    .checked_mul(2)
+                    .and_then(|doubled| doubled.checked_add(votes))
+                    .and_then(|factor| votes.checked_mul(factor)),
             }
+            .ok_or(Error::VotesOverflow)
+        }
+    }
+
+    /// How `available_votes` is replenished at the start of each epoch.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum RefillMode {
+        /// `available_votes` is set to the allowance, discarding leftovers.
+        Reset,
+        /// The allowance is added to whatever is left, once per epoch.
+        TopUp,
+    }
 
-            self.voters_addresses.push(voter);
-            self.voters.insert(voter, &Voter{reputation: 0, address: voter, available_votes});
-            
+    /// Voting epochs measured in blocks, counted from `start`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct EpochConfig {
+        start: BlockNumber,
+        length: BlockNumber,
+        allowance: u128,
+        mode: RefillMode,
+    }
+
+    impl EpochConfig {
+        /// Index of the epoch containing `block`.
+        fn epoch_of(&self, block: BlockNumber) -> BlockNumber {
+            block.saturating_sub(self.start) / self.length
+        }
+
+        /// First block of the epoch containing `block`.
+        fn epoch_start(&self, block: BlockNumber) -> BlockNumber {
+            self.start + self.epoch_of(block) * self.length
+        }
+
+        /// Applies the refills `voter` is owed for the epochs started after
+        /// `refilled_at` and up to `block`.
+        fn refill(&self, voter: &mut Voter, refilled_at: Option<BlockNumber>, block: BlockNumber) -> Result<(), Error> {
+            let missed = match refilled_at {
+                // Refills made under an earlier configuration predate
+                // `start`, in which case every epoch so far is owed.
+                Some(refilled_at) if refilled_at >= self.start => {
+                    self.epoch_of(block) - self.epoch_of(refilled_at)
+                }
+                _ => self.epoch_of(block) + 1,
+            };
+            if missed == 0 {
+                return Ok(())
+            }
+            match self.mode {
+                RefillMode::Reset => voter.available_votes = self.allowance,
+                RefillMode::TopUp => {
+                    voter.available_votes = self
+                        .allowance
+                        .checked_mul(missed.into())
+                        .and_then(|refill| voter.available_votes.checked_add(refill))
+                        .ok_or(Error::VotesOverflow)?;
+                }
+            }
             Ok(())
         }
+    }
 
-        /// Simply returns the current value of our `bool`.
-        #[ink(message)]
-        pub fn vote(&mut self, candidate_address: AccountId, votes: i128) -> Result<(), Error> {
-            let mut voter: Voter = self.voters.get(&self.env().caller()).ok_or(Error::UnregisteredVoter)?;
-            
-            if voter.available_votes < (votes.abs() as u128){
-                return Err(Error::VoterAlreadyVoted);
+    /// Fixed-point scale used by the decay arithmetic.
+    const DECAY_SCALE: u128 = 1_000_000_000_000_000_000;
+
+    /// `2^(-1/2^i)` for `i` in `1..=DECAY_FRACTION_BITS`, scaled by
+    /// `DECAY_SCALE`.
+    const DECAY_FRACTION_FACTORS: [u128; DECAY_FRACTION_BITS as usize] = [
+        707_106_781_186_547_524,
+        840_896_415_253_714_543,
+        917_004_043_204_671_231,
+        957_603_280_698_573_646,
+        978_572_062_087_700_134,
+        989_228_013_193_975_484,
+        994_599_423_483_633_175,
+        997_296_056_085_470_126,
+        998_647_112_890_970_173,
+        999_323_327_502_650_752,
+        999_661_606_496_243_683,
+        999_830_788_931_929_063,
+        999_915_390_886_613_497,
+        999_957_694_548_431_132,
+        999_978_847_050_491_929,
+        999_989_423_469_314_464,
+        999_994_711_720_674_283,
+        999_997_355_856_841_394,
+        999_998_677_927_546_759,
+        999_999_338_963_554_895,
+    ];
+
+    /// Binary digits of precision kept for partial half-lives.
+    const DECAY_FRACTION_BITS: u32 = 20;
+
+    /// Exponential reputation decay, halving reputation every `half_life`
+    /// milliseconds of block time from `since` onwards.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct DecayConfig {
+        since: Timestamp,
+        half_life: Timestamp,
+    }
+
+    impl DecayConfig {
+        /// Half-lives elapsed from `since` to `now`, in units of
+        /// `2^-DECAY_FRACTION_BITS`.
+        fn halvings(&self, now: Timestamp) -> u128 {
+            (u128::from(now.saturating_sub(self.since)) << DECAY_FRACTION_BITS) / u128::from(self.half_life)
+        }
+    }
+
+    /// Halves `reputation` once per half-life in `halvings`, which counts
+    /// in units of `2^-DECAY_FRACTION_BITS`, rounding towards zero.
+    fn decay(reputation: Reputation, halvings: u128) -> Reputation {
+        let whole = halvings >> DECAY_FRACTION_BITS;
+        if whole >= u128::BITS.into() {
+            return 0
+        }
+
+        let mut magnitude = reputation.unsigned_abs() >> whole;
+
+        // Multiply by 2^(-fraction) one binary digit of the remaining
+        // fraction of a half-life at a time.
+        let fraction = halvings & ((1 << DECAY_FRACTION_BITS) - 1);
+        for (bit, factor) in DECAY_FRACTION_FACTORS.iter().enumerate() {
+            if fraction & (1 << (DECAY_FRACTION_BITS - 1 - bit as u32)) != 0 {
+                magnitude = (magnitude / DECAY_SCALE) * factor + (magnitude % DECAY_SCALE) * factor / DECAY_SCALE;
             }
+        }
 
-            if candidate_address == voter.address {
-                return Err(Error::VoterEqualToCandidate);
+        // `magnitude` never grows, so it fits back into the original sign
+        // (`Reputation::MIN` wraps onto itself).
+        let magnitude = magnitude as Reputation;
+        if reputation < 0 { magnitude.wrapping_neg() } else { magnitude }
+    }
+
+    /// Denominator for values expressed in basis points.
+    pub const BASIS_POINTS: u16 = 10_000;
+
+    /// Multiplies `value` by `basis_points / BASIS_POINTS`, rounding towards
+    /// zero. Never overflows for `basis_points <= BASIS_POINTS`.
+    fn scale_basis_points(value: i128, basis_points: i128) -> i128 {
+        let denominator = i128::from(BASIS_POINTS);
+        value / denominator * basis_points + value % denominator * basis_points / denominator
+    }
+
+    /// Multiplies `value` by `part / whole`, rounding towards zero, for
+    /// `part <= whole`.
+    fn proportion(value: i128, part: u128, whole: u128) -> Result<i128, Error> {
+        if part == whole {
+            return Ok(value)
+        }
+        let whole = i128::try_from(whole).map_err(|_| Error::VotesOverflow)?;
+        // `part < whole`, so it fits too.
+        let part = part as i128;
+        (value % whole)
+            .checked_mul(part)
+            .and_then(|remainder| (value / whole).checked_mul(part)?.checked_add(remainder / whole))
+            .ok_or(Error::VotesOverflow)
+    }
+
+    /// Storage layout written by this code. Version 1 stored voters as
+    /// `VoterV1`; `migrate` converts them.
+    pub const STORAGE_VERSION: u32 = 2;
+
+    /// Default for the most entries a batch message accepts.
+    pub const DEFAULT_MAX_BATCH_SIZE: u32 = 50;
+
+    /// Default for the longest chain of delegations, counted in hops.
+    pub const DEFAULT_MAX_DELEGATION_DEPTH: u32 = 3;
+
+    /// Votes a voter has handed to another voter to spend on their behalf.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Delegation {
+        delegate: AccountId,
+        amount: u128,
+        /// Part of `amount` the delegate has not spent or passed on yet.
+        remaining: u128,
+        /// Votes passed on from each delegation the delegator received,
+        /// which go back to it first on revoke.
+        passed_on: Vec<(AccountId, u128)>,
+    }
+
+    /// Levels of the ranking skip list. Each level links about a quarter
+    /// of the voters of the level below, so sixteen keep searches
+    /// logarithmic for any realistic number of voters.
+    const RANK_LEVELS: usize = 16;
+
+    /// Fractional bits of the binary logarithms ranking keys are made of.
+    const RANK_FRACTION_BITS: u32 = 64;
+
+    /// Orders voters in the ranking: the sign of their reputation, then the
+    /// binary logarithm of its magnitude at decay clock zero, negated for
+    /// negative reputation. Decay scales every reputation by the same
+    /// factor, so keys stay in the order of current reputation. The
+    /// logarithm cannot tell apart magnitudes above 2^64 that differ only
+    /// in their low bits, so the written reputation itself breaks those
+    /// ties.
+    type RankKey = (i8, i128, Reputation);
+
+    /// A voter's place in the ranking.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    struct RankNode {
+        key: RankKey,
+        /// Skip list levels the voter is linked on.
+        height: u8,
+        /// Voter ranked directly above.
+        prev: Option<AccountId>,
+    }
+
+    /// Link from a voter, or the head of the ranking for `None`, to the
+    /// next voter on one skip list level.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    struct RankLink {
+        next: Option<AccountId>,
+        /// Positions the link skips, or those left to the end without a
+        /// next voter.
+        span: u32,
+    }
+
+    /// Ranking key of `reputation` written when the decay clock read
+    /// `clock`.
+    fn rank_key(reputation: Reputation, clock: u128) -> RankKey {
+        if reputation == 0 {
+            return (0, 0, 0)
+        }
+        // The clock counts half-lives in units of `2^-DECAY_FRACTION_BITS`.
+        let clock = i128::try_from(clock).unwrap_or(i128::MAX);
+        let magnitude = log2_fixed(reputation.unsigned_abs())
+            .saturating_add(clock.saturating_mul(1 << (RANK_FRACTION_BITS - DECAY_FRACTION_BITS)));
+        if reputation < 0 { (-1, -magnitude, reputation) } else { (1, magnitude, reputation) }
+    }
+
+    /// Binary logarithm of a positive `value`, with `RANK_FRACTION_BITS`
+    /// fractional bits.
+    fn log2_fixed(value: u128) -> i128 {
+        let whole = value.ilog2();
+        let mut result = i128::from(whole) << RANK_FRACTION_BITS;
+        // Normalise into `[2^63, 2^64)`, then square once per fractional
+        // bit: each square that reaches 2^64 contributes a one.
+        let mut mantissa = if whole >= 63 { value >> (whole - 63) } else { value << (63 - whole) };
+        for bit in (0..RANK_FRACTION_BITS).rev() {
+            mantissa = (mantissa * mantissa) >> 63;
+            if mantissa >= 1 << 64 {
+                mantissa >>= 1;
+                result |= 1 << bit;
+            }
+        }
+        result
+    }
+
+    /// Counts the entries of a history of `count` entries, ordered by the
+    /// block `written_at` reports for each, that were written at or before
+    /// `block`.
+    fn written_by(count: u32, block: BlockNumber, written_at: impl Fn(u32) -> Option<BlockNumber>) -> u32 {
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if written_at(mid).unwrap_or(block) <= block {
+                low = mid + 1;
+            } else {
+                high = mid;
             }
-            
-            let mut candidate: Voter = self.voters.get(candidate_address).ok_or(Error::UnregisteredVoter)?;
+        }
+        low
+    }
 
-            candidate.reputation += votes;
-            voter.available_votes -= votes.abs() as u128;
+    /// Whether the voter `account` with `key` ranks above `other` with
+    /// `other_key`.
+    fn ranks_before(key: RankKey, account: AccountId, other_key: RankKey, other: AccountId) -> bool {
+        key > other_key || (key == other_key && account < other)
+    }
 
-            self.voters.insert(candidate_address, &candidate);
-            self.voters.insert(&self.env().caller(), &voter);
+    /// What `vote` does when a voter upvotes someone who upvoted them within
+    /// the reciprocal window.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum ReciprocalPolicy {
+        #[default]
+        Allow,
+        Block,
+        /// Only this many basis points of the votes reach the candidate's
+        /// reputation; the full cost is still charged.
+        Discount(u16),
+    }
 
-            Ok(())
+    /// Limits `vote` enforces against reputation pumping. Epochs are those
+    /// configured by `set_epoch_config`; without them the limits apply over
+    /// the contract's whole lifetime.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct CollusionRules {
+        /// Most votes one voter may cast on one candidate per epoch.
+        candidate_epoch_cap: Option<u128>,
+        /// Largest share, in basis points, of a voter's budget for the epoch
+        /// that one candidate may receive. The budget is what the voter has
+        /// spent this epoch plus what they can still spend.
+        max_candidate_share: Option<u16>,
+        /// Blocks after an upvote during which upvoting back counts as
+        /// reciprocal.
+        reciprocal_window: BlockNumber,
+        reciprocal_policy: ReciprocalPolicy,
+    }
+
+    impl CollusionRules {
+        fn is_valid(&self) -> bool {
+            let discount = match self.reciprocal_policy {
+                ReciprocalPolicy::Discount(kept) => kept,
+                _ => 0,
+            };
+            self.max_candidate_share.unwrap_or(0) <= BASIS_POINTS && discount <= BASIS_POINTS
         }
+    }
 
-        #[ink(message)]
-        pub fn remove_voter(&mut self, voter_address: AccountId) -> Result<(), Error> {
-            if self.env().caller() != self.owner {
-                return Err(Error::OnlyOwnerFunction);
+    /// Function of a voter's reputation that sets their vote weight.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum WeightCurve {
+        /// Integer square root of the reputation.
+        Sqrt,
+        /// Integer base-2 logarithm of the reputation plus one.
+        Log2,
+    }
+
+    impl WeightCurve {
+        /// Applies the curve, treating negative reputation as zero.
+        fn apply(self, reputation: Reputation) -> u128 {
+            let reputation = reputation.max(0).unsigned_abs();
+            match self {
+                WeightCurve::Sqrt => reputation.isqrt(),
+                WeightCurve::Log2 => reputation.saturating_add(1).ilog2().into(),
             }
-            let mut voter: Voter = self.voters.get(&voter_address).ok_or(Error::UnregisteredVoter)?;
-            self.voters.remove(voter_address);
-            Ok(())
         }
+    }
 
-        #[ink(message)]
-        pub fn get_voters(&self) -> Result<Vec<Voter>, Error> {
-            let mut voters: Vec<Voter> = Vec::new();
-            for voter in self.voters_addresses.clone(){
-                voters.push(self.voters.get(voter).ok_or(Error::UnregisteredVoter)?);
+    /// Scales the reputation effect of each vote by the voter's own
+    /// reputation. Weights are in basis points and never exceed one, so a
+    /// vote never moves reputation by more than its raw size.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VoteWeightConfig {
+        curve: WeightCurve,
+        /// Weight, in basis points, per unit of curve output.
+        per_unit: u16,
+        /// Least weight any voter has.
+        floor: u16,
+        /// Most weight any voter has.
+        cap: u16,
+    }
+
+    impl VoteWeightConfig {
+        fn is_valid(&self) -> bool {
+            self.floor <= self.cap && self.cap <= BASIS_POINTS
+        }
+
+        fn weight(&self, reputation: Reputation) -> u16 {
+            let weight = self.curve.apply(reputation).saturating_mul(self.per_unit.into());
+            // `cap` fits in a `u16`, so the clamped weight does too.
+            weight.clamp(self.floor.into(), self.cap.into()) as u16
+        }
+    }
+
+    /// Votes and credits spent within one epoch.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    struct EpochTally {
+        /// First block of the epoch the tally belongs to.
+        epoch: BlockNumber,
+        votes: u128,
+        credits: u128,
+    }
+
+    /// Terms for joining through `request_membership` and `vouch`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VouchingConfig {
+        /// Vouches an applicant needs to become a voter.
+        required_vouches: u32,
+        /// Reputation a voter needs to vouch.
+        reputation_threshold: Reputation,
+        /// Reputation each voucher loses if the member they vouched for is
+        /// removed or slashed.
+        penalty: Reputation,
+        /// `available_votes` granted to new members.
+        allowance: u128,
+    }
+
+    /// Terms for self-registration through `register`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct RegistrationConfig {
+        deposit: Balance,
+        allowance: u128,
+        cooldown: BlockNumber,
+    }
+
+    /// Native tokens locked by a self-registered voter.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Deposit {
+        amount: Balance,
+        unlocks_at: BlockNumber,
+        /// Set when a moderator removes the voter, leaving the deposit to
+        /// `seize_deposit`.
+        frozen: bool,
+    }
+
+    /// A penalty a moderator imposed on a voter.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct SlashRecord {
+        /// Reputation taken.
+        amount: Reputation,
+        /// `available_votes` taken, which may be less than requested.
+        votes: u128,
+        /// Hash of the justification, which is kept off-chain.
+        reason_hash: Hash,
+        slashed_by: AccountId,
+        slashed_at: BlockNumber,
+        /// Last block in which the slash can be appealed and reversed.
+        appeal_until: BlockNumber,
+        /// Whether the slashed voter asked for the slash to be reversed.
+        appealed: bool,
+        reversed: bool,
+        /// Reputation taken from each category, in proportion to `amount`.
+        categories: Vec<(CategoryId, Reputation)>,
+        /// Reputation each voucher of the slashed voter lost with it.
+        penalties: Vec<(AccountId, Reputation)>,
+    }
+
+    /// A single call that cast votes, as logged for disputes.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VoteEntry {
+        voter: AccountId,
+        candidate: AccountId,
+        votes: i128,
+        /// Change to the candidate's reputation, after collusion rules.
+        effect: Reputation,
+        /// Category the effect was also credited to, if any.
+        category: Option<CategoryId>,
+        block: BlockNumber,
+        /// Decay clock when the vote was cast, which `effect` decays from.
+        decay_clock: u128,
+    }
+
+    /// Terms for `open_dispute`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct DisputeConfig {
+        /// Native tokens the disputant locks.
+        bond: Balance,
+        /// Voters selected to rule on each dispute.
+        jurors: u32,
+        /// Blocks the jurors have to rule.
+        ruling_period: BlockNumber,
+    }
+
+    impl DisputeConfig {
+        fn is_valid(&self) -> bool {
+            self.jurors > 0 && self.jurors <= MAX_JURORS && self.ruling_period > 0
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum DisputeStatus {
+        Open,
+        /// The vote's effect was reversed and the bond returned.
+        Upheld,
+        /// The bond went to the defendant.
+        Rejected,
+    }
+
+    /// A challenge against one logged vote.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Dispute {
+        vote_id: u64,
+        disputant: AccountId,
+        /// Voter that cast the disputed vote.
+        defendant: AccountId,
+        bond: Balance,
+        /// Hash of the disputant's secret, as computed by
+        /// `juror_seed_hash`, which seeds the juror draw.
+        seed_commitment: Hash,
+        /// First block in which jurors can be drawn.
+        draw_at: BlockNumber,
+        /// Jurors to draw.
+        juror_count: u32,
+        /// Empty until `draw_jurors`.
+        jurors: Vec<AccountId>,
+        /// Last block in which jurors can be drawn and rule.
+        deadline: BlockNumber,
+        uphold: u32,
+        reject: u32,
+        status: DisputeStatus,
+    }
+
+    impl Dispute {
+        /// Returns whether the dispute is upheld, or `None` while neither
+        /// side has a majority of the jurors and the deadline has not
+        /// passed. After the deadline, ties reject, as do disputes whose
+        /// jurors were never drawn.
+        fn outcome(&self, block: BlockNumber) -> Option<bool> {
+            let majority = self.jurors.len() as u32 / 2 + 1;
+            if self.uphold >= majority || self.reject >= majority {
+                Some(self.uphold >= majority)
+            } else if block > self.deadline {
+                Some(self.uphold > self.reject)
+            } else {
+                None
             }
-            Ok(voters)
-        }        
+        }
+    }
+
+    /// A commit-reveal voting round. Voters commit during
+    /// `start..commit_ends` and reveal during `commit_ends..reveal_ends`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VotingRound {
+        id: u32,
+        commit_ends: BlockNumber,
+        reveal_ends: BlockNumber,
+    }
+
+    /// A hidden vote and the `available_votes` locked with it.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Commitment {
+        hash: Hash,
+        locked: u128,
     }
-}
 
-    // / Unit tests in Rust are normally defined within such a `#[cfg(test)]`
-    // / module and test functions are marked with a `#[test]` attribute.
-    // / The below code is technically just normal Rust code.
-//     #[cfg(test)]
-//     mod tests {
-//         /// Imports all the definitions from the outer scope so we can use them here.
-//         use super::*;
-
-//         /// We test if the default constructor does its job.
-//         #[ink::test]
-//         fn default_works() {
-//             let options_and_futures = OptionsAndFutures::default();
-//             assert_eq!(options_and_futures.get(), false);
-//         }
-
-//         /// We test a simple use case of our contract.
-//         #[ink::test]
-//         fn it_works() {
-//             let mut options_and_futures = OptionsAndFutures::new(false);
-//             assert_eq!(options_and_futures.get(), false);
-//             options_and_futures.flip();
-//             assert_eq!(options_and_futures.get(), true);
-//         }
-//     }
-
-
-//     /// This is how you'd write end-to-end (E2E) or integration tests for ink! contracts.
-//     ///
-//     /// When running these you need to make sure that you:
-//     /// - Compile the tests with the `e2e-tests` feature flag enabled (`--features e2e-tests`)
-//     /// - Are running a Substrate node which contains `pallet-contracts` in the background
-//     #[cfg(all(test, feature = "e2e-tests"))]
-//     mod e2e_tests {
-//         /// Imports all the definitions from the outer scope so we can use them here.
-//         use super::*;
-
-//         /// A helper function used for calling contract messages.
-//         use ink_e2e::build_message;
-
-//         /// The End-to-End test `Result` type.
-//         type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
-
-//         /// We test that we can upload and instantiate the contract using its default constructor.
-//         #[ink_e2e::test]
-//         async fn default_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
-//             // Given
-//             let constructor = OptionsAndFuturesRef::default();
-
-//             // When
-//             let contract_account_id = client
-//                 .instantiate("options_and_futures", &ink_e2e::alice(), constructor, 0, None)
-//                 .await
-//                 .expect("instantiate failed")
-//                 .account_id;
-
-//             // Then
-//             let get = build_message::<OptionsAndFuturesRef>(contract_account_id.clone())
-//                 .call(|options_and_futures| options_and_futures.get());
-//             let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
-//             assert!(matches!(get_result.return_value(), false));
-
-//             Ok(())
-//         }
-
-//         /// We test that we can read and write a value from the on-chain contract contract.
-//         #[ink_e2e::test]
-//         async fn it_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
-//             // Given
-//             let constructor = OptionsAndFuturesRef::new(false);
-//             let contract_account_id = client
-//                 .instantiate("options_and_futures", &ink_e2e::bob(), constructor, 0, None)
-//                 .await
-//                 .expect("instantiate failed")
-//                 .account_id;
-
-//             let get = build_message::<OptionsAndFuturesRef>(contract_account_id.clone())
-//                 .call(|options_and_futures| options_and_futures.get());
-//             let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-//             assert!(matches!(get_result.return_value(), false));
-
-//             // When
-//             let flip = build_message::<OptionsAndFuturesRef>(contract_account_id.clone())
-//                 .call(|options_and_futures| options_and_futures.flip());
-//             let _flip_result = client
-//                 .call(&ink_e2e::bob(), flip, 0, None)
-//                 .await
-//                 .expect("flip failed");
-
-//             // Then
-//             let get = build_message::<OptionsAndFuturesRef>(contract_account_id.clone())
-//                 .call(|options_and_futures| options_and_futures.get());
-//             let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-//             assert!(matches!(get_result.return_value(), true));
-
-//             Ok(())
-//         }
-//     }
-// }
+    /// An active pause of the whole contract or of one operation.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct PauseState {
+        paused_at: BlockNumber,
+        /// Block at which the pause lifts by itself, if any.
+        until: Option<BlockNumber>,
+    }
+
+    impl PauseState {
+        fn is_active(&self, block: BlockNumber) -> bool {
+            self.until.is_none_or(|until| block < until)
+        }
+    }
+
+    /// Stored reputation at the end of `block`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    struct Checkpoint {
+        block: BlockNumber,
+        reputation: Reputation,
+        /// Decay clock when `reputation` was written.
+        decay_clock: u128,
+    }
+
+    /// Reputation a voter earned in one category.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    struct CategoryScore {
+        reputation: Reputation,
+        /// Decay clock when `reputation` was written, which it decays from.
+        decay_clock: u128,
+    }
+
+    /// Rules proposals are created under. Each proposal keeps the rules it
+    /// was created with.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct GovernanceConfig {
+        /// Blocks ballots are accepted for, starting the block after the
+        /// proposal is made.
+        voting_period: BlockNumber,
+        /// Least total weight of yes, no and abstain ballots for a proposal
+        /// to pass.
+        quorum: Reputation,
+        /// Least share, in basis points, of the yes and no weight that must
+        /// be yes for a proposal to pass.
+        threshold: u16,
+    }
+
+    impl GovernanceConfig {
+        fn is_valid(&self) -> bool {
+            self.voting_period > 0 && self.quorum >= 0 && self.threshold <= BASIS_POINTS
+        }
+    }
+
+    /// Parameter change a passed proposal applies when executed. Each
+    /// variant mirrors the matching `set_*` admin message.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum ProposalAction {
+        MaxBatchSize(u32),
+        MaxDelegationDepth(u32),
+        EpochConfig { length: BlockNumber, allowance: u128, mode: RefillMode },
+        ReputationHalfLife(Timestamp),
+        GovernanceConfig(GovernanceConfig),
+        CollusionRules(CollusionRules),
+        VoteWeightConfig(Option<VoteWeightConfig>),
+        SlashAppealWindow(BlockNumber),
+        DisputeConfig(DisputeConfig),
+    }
+
+    impl ProposalAction {
+        /// Decodes a call to one of the `set_*` messages a proposal can
+        /// make, encoded as a wallet would submit it: the message selector
+        /// followed by the SCALE-encoded arguments.
+        fn from_call(call: &[u8]) -> Result<Self, Error> {
+            let (selector, mut input) = call.split_first_chunk::<4>().ok_or(Error::UnsupportedCall)?;
+            let input = &mut input;
+            let action = match *selector {
+                SET_MAX_BATCH_SIZE => Self::MaxBatchSize(decode_args(input)?),
+                SET_MAX_DELEGATION_DEPTH => Self::MaxDelegationDepth(decode_args(input)?),
+                SET_EPOCH_CONFIG => {
+                    let (length, allowance, mode) = decode_args(input)?;
+                    Self::EpochConfig { length, allowance, mode }
+                }
+                SET_REPUTATION_HALF_LIFE => Self::ReputationHalfLife(decode_args(input)?),
+                SET_GOVERNANCE_CONFIG => {
+                    let (voting_period, quorum, threshold) = decode_args(input)?;
+                    Self::GovernanceConfig(GovernanceConfig { voting_period, quorum, threshold })
+                }
+                SET_COLLUSION_RULES => {
+                    let (candidate_epoch_cap, max_candidate_share, reciprocal_window, reciprocal_policy) = decode_args(input)?;
+                    Self::CollusionRules(CollusionRules {
+                        candidate_epoch_cap,
+                        max_candidate_share,
+                        reciprocal_window,
+                        reciprocal_policy,
+                    })
+                }
+                SET_VOTE_WEIGHT_CONFIG => {
+                    let (curve, per_unit, floor, cap): (Option<WeightCurve>, _, _, _) = decode_args(input)?;
+                    Self::VoteWeightConfig(curve.map(|curve| VoteWeightConfig { curve, per_unit, floor, cap }))
+                }
+                SET_SLASH_APPEAL_WINDOW => Self::SlashAppealWindow(decode_args(input)?),
+                SET_DISPUTE_CONFIG => {
+                    let (bond, jurors, ruling_period) = decode_args(input)?;
+                    Self::DisputeConfig(DisputeConfig { bond, jurors, ruling_period })
+                }
+                _ => return Err(Error::UnsupportedCall),
+            };
+            Ok(action)
+        }
+
+        /// Whether the matching `set_*` message would accept the change.
+        fn is_valid(&self) -> bool {
+            match self {
+                Self::GovernanceConfig(config) => config.is_valid(),
+                Self::CollusionRules(rules) => rules.is_valid(),
+                Self::VoteWeightConfig(config) => config.as_ref().is_none_or(VoteWeightConfig::is_valid),
+                Self::DisputeConfig(config) => config.is_valid(),
+                _ => true,
+            }
+        }
+    }
+
+    const SET_MAX_BATCH_SIZE: [u8; 4] = ink::selector_bytes!("set_max_batch_size");
+    const SET_MAX_DELEGATION_DEPTH: [u8; 4] = ink::selector_bytes!("set_max_delegation_depth");
+    const SET_EPOCH_CONFIG: [u8; 4] = ink::selector_bytes!("set_epoch_config");
+    const SET_REPUTATION_HALF_LIFE: [u8; 4] = ink::selector_bytes!("set_reputation_half_life");
+    const SET_GOVERNANCE_CONFIG: [u8; 4] = ink::selector_bytes!("set_governance_config");
+    const SET_COLLUSION_RULES: [u8; 4] = ink::selector_bytes!("set_collusion_rules");
+    const SET_VOTE_WEIGHT_CONFIG: [u8; 4] = ink::selector_bytes!("set_vote_weight_config");
+    const SET_SLASH_APPEAL_WINDOW: [u8; 4] = ink::selector_bytes!("set_slash_appeal_window");
+    const SET_DISPUTE_CONFIG: [u8; 4] = ink::selector_bytes!("set_dispute_config");
+
+    /// Decodes the arguments of an encoded call, which must use up the
+    /// whole input.
+    fn decode_args<T: scale::Decode>(input: &mut &[u8]) -> Result<T, Error> {
+        <T as scale::DecodeAll>::decode_all(input).map_err(|_| Error::UnsupportedCall)
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Ballot {
+        Yes,
+        No,
+        Abstain,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum ProposalState {
+        /// Created this block; voting opens with the next one.
+        Pending,
+        Active,
+        Defeated,
+        /// Passed and waiting for `execute`.
+        Succeeded,
+        Executed,
+    }
+
+    /// A governance proposal and its running tally. Ballots are weighted by
+    /// the voter's reputation at the end of the `snapshot` block, the one
+    /// before the proposal was made, which no transaction can change any
+    /// more, decayed up to `decay_clock`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Proposal {
+        proposer: AccountId,
+        /// Hash of the proposal text, which is kept off-chain.
+        description_hash: Hash,
+        action: Option<ProposalAction>,
+        snapshot: BlockNumber,
+        /// Decay clock when the proposal was made.
+        decay_clock: u128,
+        /// First block ballots are accepted in.
+        starts_at: BlockNumber,
+        /// Last block ballots are accepted in.
+        ends_at: BlockNumber,
+        quorum: Reputation,
+        threshold: u16,
+        yes: Reputation,
+        no: Reputation,
+        abstain: Reputation,
+        executed: bool,
+    }
+
+    impl Proposal {
+        fn passed(&self) -> bool {
+            let turnout = self.yes.saturating_add(self.no).saturating_add(self.abstain);
+            let decisive = self.yes.saturating_add(self.no);
+            // Bounding the no share instead of the yes share makes rounding
+            // err towards rejection.
+            let max_no = scale_basis_points(decisive, (BASIS_POINTS - self.threshold).into());
+            turnout >= self.quorum && self.yes > 0 && self.no <= max_no
+        }
+
+        fn state(&self, block: BlockNumber) -> ProposalState {
+            if self.executed {
+                ProposalState::Executed
+            } else if block < self.starts_at {
+                ProposalState::Pending
+            } else if block <= self.ends_at {
+                ProposalState::Active
+            } else if self.passed() {
+                ProposalState::Succeeded
+            } else {
+                ProposalState::Defeated
+            }
+        }
+    }
+
+    /// Emitted when a new voter is registered.
+    #[ink(event)]
+    pub struct VoterAdded {
+        #[ink(topic)]
+        voter: AccountId,
+        available_votes: u128,
+    }
+
+    /// Emitted when a voter is removed from the registry.
+    #[ink(event)]
+    pub struct VoterRemoved {
+        #[ink(topic)]
+        voter: AccountId,
+    }
+
+    /// Emitted every time a voter casts votes on a candidate.
+    #[ink(event)]
+    pub struct VoteCast {
+        #[ink(topic)]
+        voter: AccountId,
+        #[ink(topic)]
+        candidate: AccountId,
+        votes: i128,
+        reputation: Reputation,
+        /// Id of the vote in the log `open_dispute` refers to.
+        vote_id: u64,
+        /// Category the vote was cast in, if any.
+        category: Option<CategoryId>,
+    }
+
+    /// Emitted when an admin registers a reputation category.
+    #[ink(event)]
+    pub struct CategoryAdded {
+        #[ink(topic)]
+        category: CategoryId,
+        description_hash: Hash,
+    }
+
+    /// Emitted when a self-registered voter leaves and gets their deposit
+    /// back.
+    #[ink(event)]
+    pub struct DepositReleased {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when an admin confiscates a registration deposit.
+    #[ink(event)]
+    pub struct DepositSeized {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+        #[ink(topic)]
+        seized_by: AccountId,
+        treasury: AccountId,
+    }
+
+    /// Emitted when an account asks to be vouched in.
+    #[ink(event)]
+    pub struct MembershipRequested {
+        #[ink(topic)]
+        applicant: AccountId,
+    }
+
+    /// Emitted when a voter vouches for an applicant.
+    #[ink(event)]
+    pub struct Vouched {
+        #[ink(topic)]
+        voucher: AccountId,
+        #[ink(topic)]
+        applicant: AccountId,
+    }
+
+    /// Emitted when a voucher loses reputation because of a member they
+    /// vouched for.
+    #[ink(event)]
+    pub struct VoucherPenalised {
+        #[ink(topic)]
+        voucher: AccountId,
+        #[ink(topic)]
+        member: AccountId,
+        penalty: Reputation,
+        reputation: Reputation,
+    }
+
+    /// Emitted when a moderator slashes a voter. `index` identifies the
+    /// record among the account's slashes.
+    #[ink(event)]
+    pub struct Slashed {
+        #[ink(topic)]
+        account: AccountId,
+        index: u32,
+        amount: Reputation,
+        votes: u128,
+        reason_hash: Hash,
+        #[ink(topic)]
+        slashed_by: AccountId,
+    }
+
+    /// Emitted when a slashed voter appeals against a slash.
+    #[ink(event)]
+    pub struct SlashAppealed {
+        #[ink(topic)]
+        account: AccountId,
+        index: u32,
+    }
+
+    /// Emitted when a slash is reversed on appeal.
+    #[ink(event)]
+    pub struct SlashReversed {
+        #[ink(topic)]
+        account: AccountId,
+        index: u32,
+        #[ink(topic)]
+        reversed_by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RoundStarted {
+        #[ink(topic)]
+        round_id: u32,
+        commit_ends: BlockNumber,
+        reveal_ends: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct VoteCommitted {
+        #[ink(topic)]
+        round_id: u32,
+        #[ink(topic)]
+        voter: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct DisputeOpened {
+        #[ink(topic)]
+        dispute_id: u32,
+        vote_id: u64,
+        #[ink(topic)]
+        disputant: AccountId,
+        draw_at: BlockNumber,
+        deadline: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct JurorsDrawn {
+        #[ink(topic)]
+        dispute_id: u32,
+        jurors: Vec<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct Ruled {
+        #[ink(topic)]
+        dispute_id: u32,
+        #[ink(topic)]
+        juror: AccountId,
+        uphold: bool,
+    }
+
+    #[ink(event)]
+    pub struct DisputeResolved {
+        #[ink(topic)]
+        dispute_id: u32,
+        status: DisputeStatus,
+    }
+
+    /// Emitted when a voter takes back votes previously cast on a candidate.
+    /// `votes` carries the sign of the original votes.
+    #[ink(event)]
+    pub struct VoteRetracted {
+        #[ink(topic)]
+        voter: AccountId,
+        #[ink(topic)]
+        candidate: AccountId,
+        votes: i128,
+        reputation: Reputation,
+    }
+
+    /// Emitted when a voter delegates votes, including top-ups of an
+    /// existing delegation.
+    #[ink(event)]
+    pub struct Delegated {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        delegate: AccountId,
+        amount: u128,
+    }
+
+    /// Emitted when a delegation is revoked. `refunded` is the part of the
+    /// delegation the delegate had not spent or passed on yet.
+    #[ink(event)]
+    pub struct DelegationRevoked {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        delegate: AccountId,
+        refunded: u128,
+    }
+
+    /// Emitted when an admin changes or disables voting epochs.
+    #[ink(event)]
+    pub struct EpochConfigured {
+        config: Option<EpochConfig>,
+    }
+
+    /// Emitted when an admin changes or disables reputation decay.
+    #[ink(event)]
+    pub struct DecayConfigured {
+        config: Option<DecayConfig>,
+    }
+
+    /// Emitted when the owner nominates a new owner, who still has to
+    /// accept.
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    /// Emitted when the owner withdraws a pending nomination.
+    #[ink(event)]
+    pub struct OwnershipTransferCancelled {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        pending_owner: AccountId,
+    }
+
+    /// Emitted when `operation`, or the whole contract if `None`, is
+    /// paused.
+    #[ink(event)]
+    pub struct Paused {
+        operation: Option<Operation>,
+        until: Option<BlockNumber>,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Emitted when a pause is lifted through `unpause`. Pauses that expire
+    /// at their `until` block lift without an event.
+    #[ink(event)]
+    pub struct Unpaused {
+        operation: Option<Operation>,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ProposalCreated {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        proposer: AccountId,
+        description_hash: Hash,
+        ends_at: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct BallotCast {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        voter: AccountId,
+        ballot: Ballot,
+        weight: Reputation,
+    }
+
+    #[ink(event)]
+    pub struct ProposalExecuted {
+        #[ink(topic)]
+        proposal_id: u32,
+    }
+
+    /// Emitted when the owner replaces the contract code.
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: [u8; 32],
+    }
+
+    /// Emitted when `migrate` has converted all storage to `version`.
+    #[ink(event)]
+    pub struct StorageMigrated {
+        version: u32,
+    }
+
+    /// Emitted when `account` is granted `role` by `sender`.
+    #[ink(event)]
+    pub struct RoleGranted {
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        sender: AccountId,
+    }
+
+    /// Emitted when `role` is revoked from `account` by `sender`.
+    #[ink(event)]
+    pub struct RoleRevoked {
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        sender: AccountId,
+    }
+
+    /// Emitted when the contract owner changes, including the initial
+    /// assignment made by the constructor.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        new_owner: Option<AccountId>,
+    }
+
+    /// Defines the storage of your contract.
+    /// Add new fields to the below struct in order
+    /// to add new static storage fields to your contract.
+    ///
+    /// Only `voters_addresses` and `owner` are packed into the root cell,
+    /// as in version 1, so that upgraded code can still decode it. Any
+    /// other field must be a `Mapping` or `Lazy` with its own key.
+    #[ink(storage)]
+    pub struct OptionsAndFutures {
+        /// Voters still in the version 1 layout. They move to `voters_v2`
+        /// when next written or when `migrate` reaches them.
+        voters: Mapping<AccountId, VoterV1>,
+        /// Version 1 registry, which may repeat or list removed voters.
+        /// `migrate` drains it.
+        voters_addresses: Vec<AccountId>,
+        /// Registered voters by account.
+        voters_v2: Mapping<AccountId, Voter>,
+        /// Unset before version 2.
+        storage_version: Lazy<u32>,
+        /// Block in which `migrate` converted the last version 1 voter.
+        migrated_at: Lazy<BlockNumber>,
+        cost_model: Lazy<CostModel>,
+        epoch_config: Lazy<Option<EpochConfig>>,
+        /// First block of the epoch each voter was last refilled for.
+        refilled_at: Mapping<AccountId, BlockNumber>,
+        decay_config: Lazy<Option<DecayConfig>>,
+        /// Half-lives elapsed, in units of `2^-DECAY_FRACTION_BITS`, by the
+        /// last change of `decay_config`.
+        decay_halvings: Lazy<u128>,
+        /// Decay clock when each voter's reputation was last written.
+        reputation_clocks: Mapping<AccountId, u128>,
+        /// Outgoing delegation of each voter.
+        delegations: Mapping<AccountId, Delegation>,
+        /// Delegated votes each voter can spend on top of their own.
+        delegated_in: Mapping<AccountId, u128>,
+        /// Delegations to each voter, by the hops of the longest chain each
+        /// one completes.
+        delegation_hops: Mapping<(AccountId, u32), u32>,
+        /// Hops in the longest delegation chain ending at each voter.
+        delegation_depth: Mapping<AccountId, u32>,
+        /// Delegators whose delegation to each voter has votes left, densely
+        /// packed in `0..funding_count`.
+        funding: Mapping<(AccountId, u32), AccountId>,
+        funding_count: Mapping<AccountId, u32>,
+        /// Position of each delegator in its delegate's `funding`.
+        funding_index: Mapping<AccountId, u32>,
+        max_delegation_depth: Lazy<u32>,
+        /// Registered voters by position, densely packed in `0..voter_count`.
+        voters_by_index: Mapping<u32, AccountId>,
+        /// Position of each registered voter in `voters_by_index`.
+        voter_indices: Mapping<AccountId, u32>,
+        voter_count: Lazy<u32>,
+        /// Reputation histories by position, oldest first.
+        checkpoints: Mapping<(HistoryKey, u32), Checkpoint>,
+        checkpoint_counts: Mapping<HistoryKey, u32>,
+        /// Sum of the reputation of voters in the current layout, decayed
+        /// up to `total_decay_clock`.
+        total_reputation: Lazy<Reputation>,
+        total_decay_clock: Lazy<u128>,
+        /// Skip list of voters ordered by reputation, highest first, with
+        /// ties broken by account.
+        ranking: Mapping<AccountId, RankNode>,
+        /// Links on each level, by the voter they start from.
+        rank_links: Mapping<(RankFrom, u8), RankLink>,
+        /// Levels the head is linked on; it implicitly spans the whole
+        /// ranking on the others.
+        ranking_height: Lazy<u8>,
+        ranked_count: Lazy<u32>,
+        ranking_tail: Lazy<Option<AccountId>>,
+        collusion_rules: Lazy<CollusionRules>,
+        vote_weight_config: Lazy<Option<VoteWeightConfig>>,
+        /// Votes each `(voter, candidate)` pair cast in the latest epoch.
+        pair_tallies: Mapping<(AccountId, AccountId), EpochTally>,
+        /// Credits each voter spent in the latest epoch.
+        voter_tallies: Mapping<AccountId, EpochTally>,
+        /// Vote ledger keyed by `(voter, candidate)`.
+        vote_records: Mapping<(AccountId, AccountId), VoteRecord>,
+        /// Times each account has been removed as a voter.
+        removals: Mapping<AccountId, u32>,
+        /// Candidates each voter has voted for, by position.
+        candidates_of: Mapping<(AccountId, u32), AccountId>,
+        candidates_count: Mapping<AccountId, u32>,
+        /// Voters that have voted for each candidate, by position.
+        supporters_of: Mapping<(AccountId, u32), AccountId>,
+        supporters_count: Mapping<AccountId, u32>,
+        /// Most entries a batch message accepts, keeping batches within the
+        /// block weight limit.
+        max_batch_size: Lazy<u32>,
+        registration_config: Lazy<Option<RegistrationConfig>>,
+        /// Deposits locked by self-registered voters.
+        deposits: Mapping<AccountId, Deposit>,
+        /// Account seized deposits are sent to.
+        treasury: Lazy<Option<AccountId>>,
+        vouching_config: Lazy<Option<VouchingConfig>>,
+        /// Vouches collected so far by each pending applicant.
+        applications: Mapping<AccountId, Vec<AccountId>>,
+        /// Voters that vouched each member in.
+        vouchers: Mapping<AccountId, Vec<AccountId>>,
+        /// Vouching penalty in force when each member was vouched in.
+        vouch_penalties: Mapping<AccountId, Reputation>,
+        /// Slashes imposed on each account, by position.
+        slashes: Mapping<(AccountId, u32), SlashRecord>,
+        slash_counts: Mapping<AccountId, u32>,
+        /// Blocks after a slash during which it can be reversed.
+        slash_appeal_window: Lazy<BlockNumber>,
+        /// Every vote cast, by id.
+        vote_log: Mapping<u64, VoteEntry>,
+        vote_log_count: Lazy<u64>,
+        dispute_config: Lazy<Option<DisputeConfig>>,
+        disputes: Mapping<u32, Dispute>,
+        dispute_count: Lazy<u32>,
+        /// Hash chain over votes, ballots, rulings and disputes, by
+        /// position.
+        entropy: Mapping<u32, EntropyLink>,
+        entropy_count: Lazy<u32>,
+        /// Description hash of each registered category, by id.
+        categories: Mapping<CategoryId, Hash>,
+        category_count: Lazy<u32>,
+        /// Reputation each voter earned through votes in each category.
+        category_reputation: Mapping<(AccountId, CategoryId), CategoryScore>,
+        /// Latest commit-reveal round, running or not.
+        round: Lazy<Option<VotingRound>>,
+        /// Commitments by `(round id, voter)`.
+        commitments: Mapping<(u32, AccountId), Commitment>,
+        /// Dispute opened against each vote, by vote id.
+        vote_disputes: Mapping<u64, u32>,
+        /// Ruling of each juror on each dispute, `true` to uphold.
+        rulings: Mapping<(u32, AccountId), bool>,
+        governance_config: Lazy<Option<GovernanceConfig>>,
+        proposals: Mapping<u32, Proposal>,
+        proposal_count: Lazy<u32>,
+        /// Ballot each voter cast on each proposal.
+        ballots: Mapping<(u32, AccountId), Ballot>,
+        /// Roles explicitly granted to each account.
+        roles: Mapping<(AccountId, Role), ()>,
+        /// Pause of the whole contract, which blocks every operation.
+        paused: Lazy<Option<PauseState>>,
+        /// Pauses of single operations.
+        paused_operations: Mapping<Operation, PauseState>,
+        owner: AccountId,
+        /// Set once ownership has been renounced, which `owner` cannot
+        /// express.
+        ownership_renounced: Lazy<bool>,
+        /// Account nominated by `transfer_ownership`, until it accepts.
+        pending_owner: Lazy<Option<AccountId>>,
+    }
+
+    impl OptionsAndFutures {
+        /// Creates a registry owned by the caller where every vote costs one
+        /// credit.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::with_cost_model(CostModel::Linear)
+        }
+
+        /// Creates a registry owned by the caller that charges votes
+        /// according to `cost_model`.
+        #[ink(constructor)]
+        pub fn with_cost_model(cost_model: CostModel) -> Self {
+            let voters = Mapping::default();
+            let owner = Self::env().caller();
+
+            Self::env().emit_event(OwnershipTransferred {
+                previous_owner: None,
+                new_owner: Some(owner),
+            });
+
+            let mut contract = Self {
+                voters,
+                voters_addresses: Vec::new(),
+                voters_v2: Mapping::default(),
+                storage_version: Lazy::default(),
+                migrated_at: Lazy::default(),
+                cost_model: Lazy::default(),
+                epoch_config: Lazy::default(),
+                refilled_at: Mapping::default(),
+                decay_config: Lazy::default(),
+                decay_halvings: Lazy::default(),
+                reputation_clocks: Mapping::default(),
+                delegations: Mapping::default(),
+                delegated_in: Mapping::default(),
+                delegation_hops: Mapping::default(),
+                delegation_depth: Mapping::default(),
+                funding: Mapping::default(),
+                funding_count: Mapping::default(),
+                funding_index: Mapping::default(),
+                max_delegation_depth: Lazy::default(),
+                max_batch_size: Lazy::default(),
+                registration_config: Lazy::default(),
+                deposits: Mapping::default(),
+                treasury: Lazy::default(),
+                vouching_config: Lazy::default(),
+                applications: Mapping::default(),
+                vouchers: Mapping::default(),
+                vouch_penalties: Mapping::default(),
+                slashes: Mapping::default(),
+                slash_counts: Mapping::default(),
+                slash_appeal_window: Lazy::default(),
+                vote_log: Mapping::default(),
+                vote_log_count: Lazy::default(),
+                dispute_config: Lazy::default(),
+                disputes: Mapping::default(),
+                dispute_count: Lazy::default(),
+                entropy: Mapping::default(),
+                entropy_count: Lazy::default(),
+                vote_disputes: Mapping::default(),
+                categories: Mapping::default(),
+                category_count: Lazy::default(),
+                category_reputation: Mapping::default(),
+                round: Lazy::default(),
+                commitments: Mapping::default(),
+                rulings: Mapping::default(),
+                governance_config: Lazy::default(),
+                proposals: Mapping::default(),
+                proposal_count: Lazy::default(),
+                ballots: Mapping::default(),
+                roles: Mapping::default(),
+                paused: Lazy::default(),
+                paused_operations: Mapping::default(),
+                voters_by_index: Mapping::default(),
+                voter_indices: Mapping::default(),
+                voter_count: Lazy::default(),
+                checkpoints: Mapping::default(),
+                checkpoint_counts: Mapping::default(),
+                total_reputation: Lazy::default(),
+                total_decay_clock: Lazy::default(),
+                ranking: Mapping::default(),
+                rank_links: Mapping::default(),
+                ranking_height: Lazy::default(),
+                ranked_count: Lazy::default(),
+                ranking_tail: Lazy::default(),
+                collusion_rules: Lazy::default(),
+                vote_weight_config: Lazy::default(),
+                pair_tallies: Mapping::default(),
+                voter_tallies: Mapping::default(),
+                vote_records: Mapping::default(),
+                removals: Mapping::default(),
+                candidates_of: Mapping::default(),
+                candidates_count: Mapping::default(),
+                supporters_of: Mapping::default(),
+                supporters_count: Mapping::default(),
+                owner,
+                ownership_renounced: Lazy::default(),
+                pending_owner: Lazy::default(),
+            };
+            contract.storage_version.set(&STORAGE_VERSION);
+            contract.cost_model.set(&cost_model);
+            contract
+        }
+
+        /// Nominates `new_owner`, who becomes the owner once they call
+        /// `accept_ownership`. Replaces any earlier nomination.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            let owner = self.ensure_owner()?;
+            self.pending_owner.set(&Some(new_owner));
+
+            self.env().emit_event(OwnershipTransferStarted {
+                previous_owner: owner,
+                new_owner,
+            });
+
+            Ok(())
+        }
+
+        /// Completes a transfer started by `transfer_ownership`. Must be
+        /// called by the nominated account.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.pending_owner() != Some(caller) {
+                return Err(Error::NotPendingOwner);
+            }
+
+            let previous_owner = self.owner();
+            self.owner = caller;
+            self.pending_owner.set(&None);
+
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: Some(caller),
+            });
+
+            Ok(())
+        }
+
+        /// Withdraws the pending nomination, if any.
+        #[ink(message)]
+        pub fn cancel_ownership_transfer(&mut self) -> Result<(), Error> {
+            let owner = self.ensure_owner()?;
+            let pending_owner = self.pending_owner().ok_or(Error::NoPendingOwnership)?;
+            self.pending_owner.set(&None);
+
+            self.env().emit_event(OwnershipTransferCancelled { owner, pending_owner });
+
+            Ok(())
+        }
+
+        /// Gives up ownership for good, along with any pending nomination.
+        /// Explicitly granted roles are unaffected.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self) -> Result<(), Error> {
+            let owner = self.ensure_owner()?;
+            self.ownership_renounced.set(&true);
+            self.pending_owner.set(&None);
+
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: Some(owner),
+                new_owner: None,
+            });
+
+            Ok(())
+        }
+
+        /// Replaces the contract code with the code behind `code_hash`,
+        /// keeping storage. Call `migrate` afterwards if the new code bumps
+        /// `STORAGE_VERSION`. Only the owner may call this.
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: [u8; 32]) -> Result<(), Error> {
+            self.ensure_owner()?;
+            ink::env::set_code_hash(&code_hash).map_err(|_| Error::UpgradeFailed)?;
+            self.env().emit_event(CodeUpgraded { code_hash });
+            Ok(())
+        }
+
+        /// Converts up to `limit` voters left in the version 1 layout,
+        /// taking them off the end of the version 1 registry. Voters are
+        /// also converted whenever they are written, and read correctly
+        /// either way, but listings and rankings leave them out until then.
+        /// Returns how many registry entries are left to visit; at zero the
+        /// storage version is bumped. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn migrate(&mut self, limit: u32) -> Result<u32, Error> {
+            self.ensure_role(Role::Admin)?;
+            if self.storage_version() >= STORAGE_VERSION {
+                return Ok(0);
+            }
+
+            for _ in 0..limit {
+                let Some(account) = self.voters_addresses.pop() else {
+                    break
+                };
+                // Entries of removed or already converted voters, and
+                // repeated entries, find nothing left to convert.
+                self.convert_voter(account);
+            }
+
+            let left = self.voters_addresses.len() as u32;
+            if left == 0 {
+                self.storage_version.set(&STORAGE_VERSION);
+                self.migrated_at.set(&self.env().block_number());
+                self.env().emit_event(StorageMigrated { version: STORAGE_VERSION });
+            }
+
+            Ok(left)
+        }
+
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            self.storage_version.get().unwrap_or(1)
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> Option<AccountId> {
+            (!self.ownership_renounced.get_or_default()).then_some(self.owner)
+        }
+
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner.get_or_default()
+        }
+
+        /// Grants `role` to `account`. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+
+            if !self.roles.contains((account, role)) {
+                self.roles.insert((account, role), &());
+                self.env().emit_event(RoleGranted { role, account, sender: self.env().caller() });
+            }
+
+            Ok(())
+        }
+
+        /// Revokes an explicitly granted `role` from `account`. Requires the
+        /// `Admin` role.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+
+            if self.roles.contains((account, role)) {
+                self.roles.remove((account, role));
+                self.env().emit_event(RoleRevoked { role, account, sender: self.env().caller() });
+            }
+
+            Ok(())
+        }
+
+        /// Returns whether `account` holds `role`, either explicitly or
+        /// through being the owner or an `Admin`.
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            Some(account) == self.owner()
+                || self.roles.contains((account, role))
+                || self.roles.contains((account, Role::Admin))
+        }
+
+        /// Pauses `operation`, or every operation if `None`, until `unpause`
+        /// is called or, if given, until block `until`. Pausing again
+        /// replaces the earlier `until`. Queries keep working. Requires the
+        /// `Pauser` role.
+        #[ink(message)]
+        pub fn pause(&mut self, operation: Option<Operation>, until: Option<BlockNumber>) -> Result<(), Error> {
+            self.ensure_role(Role::Pauser)?;
+            let block = self.env().block_number();
+            if until.is_some_and(|until| until <= block) {
+                return Err(Error::InvalidConfig);
+            }
+
+            let state = PauseState { paused_at: block, until };
+            match operation {
+                Some(operation) => {
+                    self.paused_operations.insert(operation, &state);
+                }
+                None => self.paused.set(&Some(state)),
+            }
+            self.env().emit_event(Paused { operation, until, account: self.env().caller() });
+
+            Ok(())
+        }
+
+        /// Lifts the pause of `operation`, or of the whole contract if
+        /// `None`. An operation stays blocked while the whole contract is
+        /// paused. Requires the `Pauser` role.
+        #[ink(message)]
+        pub fn unpause(&mut self, operation: Option<Operation>) -> Result<(), Error> {
+            self.ensure_role(Role::Pauser)?;
+            if self.pause_state(operation).is_none() {
+                return Err(Error::NotPaused);
+            }
+
+            match operation {
+                Some(operation) => self.paused_operations.remove(operation),
+                None => self.paused.set(&None),
+            }
+            self.env().emit_event(Unpaused { operation, account: self.env().caller() });
+
+            Ok(())
+        }
+
+        /// Returns the active pause of `operation` on its own, or of the
+        /// whole contract if `None`.
+        #[ink(message)]
+        pub fn pause_state(&self, operation: Option<Operation>) -> Option<PauseState> {
+            let state = match operation {
+                Some(operation) => self.paused_operations.get(operation),
+                None => self.paused.get_or_default(),
+            };
+            state.filter(|state| state.is_active(self.env().block_number()))
+        }
+
+        /// Returns whether `operation` is blocked, either on its own or
+        /// because the whole contract is paused.
+        #[ink(message)]
+        pub fn is_paused(&self, operation: Operation) -> bool {
+            self.pause_state(None).is_some() || self.pause_state(Some(operation)).is_some()
+        }
+
+        /// Registers `voter` with `available_votes` to spend. Requires the
+        /// `Registrar` role.
+        #[ink(message)]
+        pub fn add_voter(&mut self, voter: AccountId, available_votes: u128) -> Result<(), Error> {
+            self.ensure_role(Role::Registrar)?;
+            self.ensure_not_paused(Operation::Registration)?;
+            self.register_voter(voter, available_votes)
+        }
+
+        /// Registers every `(voter, available_votes)` pair, all or nothing:
+        /// the first failing entry is reported by index and the returned
+        /// error reverts the whole call. Requires the `Registrar` role.
+        #[ink(message)]
+        pub fn add_voters(&mut self, voters: Vec<(AccountId, u128)>) -> Result<(), Error> {
+            self.ensure_role(Role::Registrar)?;
+            self.ensure_not_paused(Operation::Registration)?;
+            self.ensure_batch_size(voters.len())?;
+            for (index, (voter, available_votes)) in voters.into_iter().enumerate() {
+                self.register_voter(voter, available_votes)
+                    .map_err(|error| Error::BatchEntryFailed(index as u32, Box::new(error)))?;
+            }
+            Ok(())
+        }
+
+        /// Removes every listed voter, all or nothing. Requires the
+        /// `Moderator` role.
+        #[ink(message)]
+        pub fn remove_voters(&mut self, voters: Vec<AccountId>) -> Result<(), Error> {
+            self.ensure_role(Role::Moderator)?;
+            self.ensure_not_paused(Operation::Removal)?;
+            self.ensure_batch_size(voters.len())?;
+            for (index, voter) in voters.into_iter().enumerate() {
+                self.unregister_voter(voter)
+                    .map_err(|error| Error::BatchEntryFailed(index as u32, Box::new(error)))?;
+                self.freeze_deposit(voter);
+            }
+            Ok(())
+        }
+
+        /// Lets the caller join as a voter by locking exactly the configured
+        /// registration deposit. The deposit is returned by `deregister`
+        /// once the cooldown has passed.
+        #[ink(message, payable)]
+        pub fn register(&mut self) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Registration)?;
+            let caller = self.env().caller();
+            let config = self.registration_config.get_or_default().ok_or(Error::RegistrationClosed)?;
+            if self.env().transferred_value() != config.deposit {
+                return Err(Error::WrongDeposit);
+            }
+            if self.is_voter(caller) {
+                return Err(Error::VoterAlreadyRegistered);
+            }
+            if self.deposits.contains(caller) {
+                return Err(Error::DepositPending);
+            }
+
+            self.register_voter(caller, config.allowance)?;
+            self.deposits.insert(caller, &Deposit {
+                amount: config.deposit,
+                unlocks_at: self.env().block_number().saturating_add(config.cooldown),
+                frozen: false,
+            });
+
+            Ok(())
+        }
+
+        /// Leaves the registry and refunds the caller's registration
+        /// deposit. Deposits frozen by a moderator's removal are left to
+        /// `seize_deposit`.
+        #[ink(message)]
+        pub fn deregister(&mut self) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Removal)?;
+            let caller = self.env().caller();
+            let deposit = self.deposits.get(caller).ok_or(Error::NoDeposit)?;
+            if deposit.frozen {
+                return Err(Error::DepositFrozen);
+            }
+            if self.env().block_number() < deposit.unlocks_at {
+                return Err(Error::CooldownActive);
+            }
+
+            if self.is_voter(caller) {
+                self.unregister_voter(caller)?;
+            }
+            self.deposits.remove(caller);
+            self.env().transfer(caller, deposit.amount).map_err(|_| Error::TransferFailed)?;
+
+            self.env().emit_event(DepositReleased { account: caller, amount: deposit.amount });
+
+            Ok(())
+        }
+
+        /// Removes `account` from the registry if still registered and
+        /// sends its registration deposit to the treasury. Requires the
+        /// `Admin` role.
+        #[ink(message)]
+        pub fn seize_deposit(&mut self, account: AccountId) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.ensure_not_paused(Operation::Removal)?;
+            let deposit = self.deposits.get(account).ok_or(Error::NoDeposit)?;
+            let treasury = self.treasury.get_or_default().ok_or(Error::NoTreasury)?;
+
+            if self.is_voter(account) {
+                self.unregister_voter(account)?;
+            }
+            self.deposits.remove(account);
+            self.env().transfer(treasury, deposit.amount).map_err(|_| Error::TransferFailed)?;
+
+            self.env().emit_event(DepositSeized {
+                account,
+                amount: deposit.amount,
+                seized_by: self.env().caller(),
+                treasury,
+            });
+
+            Ok(())
+        }
+
+        /// Opens self-registration through `register`, which will require
+        /// a `deposit` of exactly this size, grant `allowance` votes and
+        /// lock the deposit for `cooldown` blocks. Deposits already locked
+        /// keep their terms. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn set_registration_config(&mut self, deposit: Balance, allowance: u128, cooldown: BlockNumber) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.registration_config.set(&Some(RegistrationConfig { deposit, allowance, cooldown }));
+            Ok(())
+        }
+
+        /// Closes self-registration. Deposits already locked can still be
+        /// reclaimed through `deregister`. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn clear_registration_config(&mut self) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.registration_config.set(&None);
+            Ok(())
+        }
+
+        /// Sets the account seized deposits are sent to. Requires the
+        /// `Admin` role.
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: AccountId) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.treasury.set(&Some(treasury));
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn treasury(&self) -> Option<AccountId> {
+            self.treasury.get_or_default()
+        }
+
+        #[ink(message)]
+        pub fn registration_config(&self) -> Option<RegistrationConfig> {
+            self.registration_config.get_or_default()
+        }
+
+        #[ink(message)]
+        pub fn deposit_of(&self, account: AccountId) -> Option<Deposit> {
+            self.deposits.get(account)
+        }
+
+        /// Asks to join as a voter. The caller becomes one once enough
+        /// voters `vouch` for them.
+        #[ink(message)]
+        pub fn request_membership(&mut self) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Registration)?;
+            let caller = self.env().caller();
+            if self.vouching_config.get_or_default().is_none() {
+                return Err(Error::VouchingClosed);
+            }
+            if self.is_voter(caller) {
+                return Err(Error::VoterAlreadyRegistered);
+            }
+            if self.applications.contains(caller) {
+                return Err(Error::MembershipAlreadyRequested);
+            }
+
+            self.applications.insert(caller, &Vec::<AccountId>::new());
+            self.env().emit_event(MembershipRequested { applicant: caller });
+
+            Ok(())
+        }
+
+        /// Vouches for `applicant`, registering them once they have the
+        /// required number of vouches. The caller must be a voter with at
+        /// least the threshold reputation, and is penalised if the new
+        /// member is later removed or slashed. Earlier vouches from voters
+        /// that have since left or fallen below the threshold are dropped.
+        #[ink(message)]
+        pub fn vouch(&mut self, applicant: AccountId) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Registration)?;
+            let caller = self.env().caller();
+            let config = self.vouching_config.get_or_default().ok_or(Error::VouchingClosed)?;
+            let voucher: Voter = self.load_voter(caller)?;
+            if voucher.reputation < config.reputation_threshold {
+                return Err(Error::ReputationTooLow);
+            }
+            let mut vouchers = self.applications.get(applicant).ok_or(Error::NoMembershipRequest)?;
+            if vouchers.contains(&caller) {
+                return Err(Error::AlreadyVouched);
+            }
+
+            vouchers.retain(|voucher| {
+                self.load_voter(*voucher)
+                    .is_ok_and(|voucher| voucher.reputation >= config.reputation_threshold)
+            });
+            vouchers.push(caller);
+            self.env().emit_event(Vouched { voucher: caller, applicant });
+
+            if vouchers.len() as u32 >= config.required_vouches {
+                self.register_voter(applicant, config.allowance)?;
+                self.vouchers.insert(applicant, &vouchers);
+                self.vouch_penalties.insert(applicant, &config.penalty);
+            } else {
+                self.applications.insert(applicant, &vouchers);
+            }
+
+            Ok(())
+        }
+
+        /// Opens membership by vouching: applicants need `required_vouches`
+        /// vouches from voters with at least `reputation_threshold`
+        /// reputation, and join with `allowance` votes. Each voucher loses
+        /// `penalty` reputation if the member is removed or slashed; members
+        /// already vouched in keep the penalty they were admitted under.
+        /// Requires the `Admin` role.
+        #[ink(message)]
+        pub fn set_vouching_config(
+            &mut self,
+            required_vouches: u32,
+            reputation_threshold: Reputation,
+            penalty: Reputation,
+            allowance: u128,
+        ) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            if required_vouches == 0 || penalty < 0 {
+                return Err(Error::InvalidConfig);
+            }
+
+            self.vouching_config.set(&Some(VouchingConfig {
+                required_vouches,
+                reputation_threshold,
+                penalty,
+                allowance,
+            }));
+
+            Ok(())
+        }
+
+        /// Closes membership by vouching. Pending applications can no
+        /// longer collect vouches, while vouchers of existing members stay
+        /// liable. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn clear_vouching_config(&mut self) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.vouching_config.set(&None);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn vouching_config(&self) -> Option<VouchingConfig> {
+            self.vouching_config.get_or_default()
+        }
+
+        /// Returns the vouches a pending applicant has collected so far.
+        #[ink(message)]
+        pub fn membership_request(&self, applicant: AccountId) -> Option<Vec<AccountId>> {
+            self.applications.get(applicant)
+        }
+
+        /// Returns the voters that vouched `member` in.
+        #[ink(message)]
+        pub fn vouchers_of(&self, member: AccountId) -> Vec<AccountId> {
+            self.vouchers.get(member).unwrap_or_default()
+        }
+
+        /// Casts `votes` on `candidate_address`. Positive votes raise the
+        /// candidate's reputation and negative votes lower it; either way
+        /// the caller pays `vote_cost` from their `available_votes`. The
+        /// effect is also credited to the candidate's reputation in
+        /// `category`, if any.
+        #[ink(message)]
+        pub fn vote(&mut self, candidate_address: AccountId, votes: i128, category: Option<CategoryId>) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Voting)?;
+            self.ensure_no_round()?;
+            self.cast_vote(self.env().caller(), candidate_address, votes, category)
+        }
+
+        /// Registers a reputation category described off-chain by
+        /// `description_hash` and returns its id. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn add_category(&mut self, description_hash: Hash) -> Result<CategoryId, Error> {
+            self.ensure_role(Role::Admin)?;
+            if self.category_count.get_or_default() >= MAX_CATEGORIES {
+                return Err(Error::TooManyCategories);
+            }
+
+            let category = self.category_count.get_or_default();
+            self.categories.insert(category, &description_hash);
+            self.category_count.set(&(category + 1));
+            self.env().emit_event(CategoryAdded { category, description_hash });
+
+            Ok(category)
+        }
+
+        /// Returns the description hash of `category`.
+        #[ink(message)]
+        pub fn category(&self, category: CategoryId) -> Option<Hash> {
+            self.categories.get(category)
+        }
+
+        /// Returns how many categories have been added. Their ids run from
+        /// zero up to, but not including, the count.
+        #[ink(message)]
+        pub fn category_count(&self) -> u32 {
+            self.category_count.get_or_default()
+        }
+
+        /// Returns the reputation `account` earned through votes in
+        /// `category`, net of retracted votes, upheld disputes and slashes,
+        /// and decayed like the aggregate reputation.
+        #[ink(message)]
+        pub fn reputation_in(&self, account: AccountId, category: CategoryId) -> Reputation {
+            self.category_reputation
+                .get((account, category))
+                .map_or(0, |score| self.decayed(score.reputation, Some(score.decay_clock)))
+        }
+
+        /// Returns `account`'s reputation in every category, by category
+        /// id, followed by the aggregate reputation of `get_voter`, which
+        /// also reflects uncategorized votes and voucher penalties.
+        /// Returns `None` for accounts that are not voters.
+        #[ink(message)]
+        pub fn reputation_by_category(&self, account: AccountId) -> Option<(Vec<Reputation>, Reputation)> {
+            let voter = self.load_voter(account).ok()?;
+            let by_category = (0..self.category_count.get_or_default())
+                .map(|category| self.reputation_in(account, category))
+                .collect();
+            Some((by_category, voter.reputation))
+        }
+
+        /// Casts every `(candidate, votes, category)` entry in order, all or
+        /// nothing. Later entries see the state left by earlier ones, so the
+        /// same candidate may appear more than once.
+        #[ink(message)]
+        pub fn vote_many(&mut self, votes: Vec<(AccountId, i128, Option<CategoryId>)>) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Voting)?;
+            self.ensure_no_round()?;
+            self.ensure_batch_size(votes.len())?;
+            let caller = self.env().caller();
+            for (index, (candidate, votes, category)) in votes.into_iter().enumerate() {
+                self.cast_vote(caller, candidate, votes, category)
+                    .map_err(|error| Error::BatchEntryFailed(index as u32, Box::new(error)))?;
+            }
+            Ok(())
+        }
+
+        /// Takes back `amount` of the votes the caller has cast on
+        /// `candidate_address`, refunding their cost to the caller's
+        /// `available_votes` and reversing their effect on the candidate's
+        /// reputation.
+        #[ink(message)]
+        pub fn retract_vote(&mut self, candidate_address: AccountId, amount: u128) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Voting)?;
+            self.ensure_no_round()?;
+            self.retract(self.env().caller(), candidate_address, amount)?;
+            Ok(())
+        }
+
+        /// Moves `amount` of the votes the caller has cast on `from` to `to`,
+        /// keeping their direction and casting them in `category`, if any.
+        #[ink(message)]
+        pub fn reallocate(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+            category: Option<CategoryId>,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Voting)?;
+            self.ensure_no_round()?;
+            let caller = self.env().caller();
+            if to == caller {
+                return Err(Error::VoterEqualToCandidate);
+            }
+            if !self.is_voter(to) {
+                return Err(Error::UnregisteredVoter);
+            }
+
+            if category.is_some_and(|category| !self.categories.contains(category)) {
+                return Err(Error::UnknownCategory);
+            }
+
+            let votes = self.retract(caller, from, amount)?;
+            self.cast_vote(caller, to, votes, category)
+        }
+
+        /// Starts a commit-reveal round with a commit phase of
+        /// `commit_blocks` blocks followed by a reveal phase of
+        /// `reveal_blocks` blocks. Public voting is closed until the round
+        /// ends. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn start_round(&mut self, commit_blocks: BlockNumber, reveal_blocks: BlockNumber) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.ensure_no_round()?;
+            if commit_blocks == 0 || reveal_blocks == 0 {
+                return Err(Error::InvalidConfig);
+            }
+
+            let round_id = self.round.get_or_default().map_or(0, |round| round.id + 1);
+            let commit_ends = self.env().block_number().saturating_add(commit_blocks);
+            let reveal_ends = commit_ends.saturating_add(reveal_blocks);
+            self.round.set(&Some(VotingRound { id: round_id, commit_ends, reveal_ends }));
+
+            self.env().emit_event(RoundStarted { round_id, commit_ends, reveal_ends });
+
+            Ok(())
+        }
+
+        /// Returns the latest round, which has ended once its
+        /// `reveal_ends` block is reached.
+        #[ink(message)]
+        pub fn current_round(&self) -> Option<VotingRound> {
+            self.round.get_or_default()
+        }
+
+        /// Commits to a vote hidden behind `commitment`, as computed by
+        /// `commitment_hash`, locking `locked` of the caller's own
+        /// `available_votes`. The locked votes pay for the vote on reveal,
+        /// with whatever it does not cost refunded, and are forfeited if it
+        /// is never revealed. Epoch refills wait until the round ends or
+        /// the vote is revealed.
+        #[ink(message)]
+        pub fn commit(&mut self, commitment: Hash, locked: u128) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Voting)?;
+            let round = self.round.get_or_default().filter(|round| self.env().block_number() < round.commit_ends);
+            let round = round.ok_or(Error::NotCommitPhase)?;
+            let caller = self.env().caller();
+            let mut voter: Voter = self.load_voter(caller)?;
+            if self.commitments.contains((round.id, caller)) {
+                return Err(Error::AlreadyCommitted);
+            }
+            if locked == 0 {
+                return Err(Error::ZeroVotes);
+            }
+
+            voter.available_votes = voter.available_votes.checked_sub(locked).ok_or(Error::InsufficientVotes)?;
+            self.store_voter(&voter);
+            self.commitments.insert((round.id, caller), &Commitment { hash: commitment, locked });
+
+            self.env().emit_event(VoteCommitted { round_id: round.id, voter: caller });
+
+            Ok(())
+        }
+
+        /// Opens the caller's commitment for the current round and casts
+        /// the committed vote, after returning the locked votes. The vote
+        /// must cost no more than the locked votes and goes through the
+        /// same checks as `vote`; if they fail the commitment stays
+        /// unrevealed.
+        #[ink(message)]
+        pub fn reveal(
+            &mut self,
+            candidate_address: AccountId,
+            votes: i128,
+            category: Option<CategoryId>,
+            salt: [u8; 32],
+        ) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Voting)?;
+            let block = self.env().block_number();
+            let round = self.round.get_or_default().filter(|round| round.commit_ends <= block && block < round.reveal_ends);
+            let round = round.ok_or(Error::NotRevealPhase)?;
+            let caller = self.env().caller();
+            let commitment = self.commitments.get((round.id, caller)).ok_or(Error::NoCommitment)?;
+            if commitment.hash != self.commitment_hash(caller, round.id, candidate_address, votes, category, salt) {
+                return Err(Error::InvalidReveal);
+            }
+            if self.vote_cost(caller, candidate_address, votes)? > commitment.locked {
+                return Err(Error::ExceedsLockedVotes);
+            }
+
+            let mut voter: Voter = self.load_voter(caller)?;
+            voter.available_votes = voter.available_votes.checked_add(commitment.locked).ok_or(Error::VotesOverflow)?;
+            self.store_voter(&voter);
+            self.commitments.remove((round.id, caller));
+
+            self.cast_vote(caller, candidate_address, votes, category)
+        }
+
+        #[ink(message)]
+        pub fn commitment_of(&self, round_id: u32, voter: AccountId) -> Option<Commitment> {
+            self.commitments.get((round_id, voter))
+        }
+
+        /// Returns the hash `voter` commits to in round `round_id` for
+        /// casting `votes` on `candidate_address` in `category`, if any,
+        /// blinded by a secret `salt`.
+        #[ink(message)]
+        pub fn commitment_hash(
+            &self,
+            voter: AccountId,
+            round_id: u32,
+            candidate_address: AccountId,
+            votes: i128,
+            category: Option<CategoryId>,
+            salt: [u8; 32],
+        ) -> Hash {
+            let preimage = (voter, round_id, candidate_address, votes, category, salt);
+            Hash::from(self.env().hash_encoded::<ink::env::hash::Blake2x256, _>(&preimage))
+        }
+
+        /// Hands `amount` of the caller's votes to `delegate`, who can spend
+        /// them through `vote` or pass them further down a chain of at most
+        /// `max_delegation_depth` hops. Delegated votes the caller received
+        /// are passed on before their own. A voter delegates to one delegate
+        /// at a time; calling again with the same delegate tops it up.
+        #[ink(message)]
+        pub fn delegate(&mut self, delegate: AccountId, amount: u128) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Delegation)?;
+            if amount == 0 {
+                return Err(Error::ZeroVotes);
+            }
+            let caller = self.env().caller();
+            let mut voter: Voter = self.load_voter(caller)?;
+            if delegate == caller {
+                return Err(Error::SelfDelegation);
+            }
+            if !self.is_voter(delegate) {
+                return Err(Error::UnregisteredVoter);
+            }
+
+            // Pass on received votes first, then the caller's own.
+            let received = self.delegated_in.get(caller).unwrap_or(0);
+            if voter.available_votes.saturating_add(received) < amount {
+                return Err(Error::InsufficientVotes);
+            }
+            let delegate_received = self
+                .delegated_in
+                .get(delegate)
+                .unwrap_or(0)
+                .checked_add(amount)
+                .ok_or(Error::VotesOverflow)?;
+
+            let mut delegation = match self.delegations.get(caller) {
+                Some(delegation) if delegation.delegate != delegate => return Err(Error::DelegationExists),
+                Some(delegation) => delegation,
+                None => {
+                    // Walk the chain below `delegate`, which must neither
+                    // lead back to the caller nor grow past the depth limit.
+                    let mut chain = Vec::new();
+                    let mut next = delegate;
+                    while let Some(delegation) = self.delegations.get(next) {
+                        if delegation.delegate == caller {
+                            return Err(Error::DelegationCycle);
+                        }
+                        chain.push(delegation.delegate);
+                        next = delegation.delegate;
+                    }
+                    let depth = self.delegation_depth.get(caller).unwrap_or(0) + 1;
+                    if depth + chain.len() as u32 > self.max_delegation_depth() {
+                        return Err(Error::DelegationTooDeep);
+                    }
+
+                    self.shift_delegation_hops(delegate, 0, depth);
+                    Delegation { delegate, amount: 0, remaining: 0, passed_on: Vec::new() }
+                }
+            };
+
+            delegation.amount = delegation.amount.checked_add(amount).ok_or(Error::VotesOverflow)?;
+            delegation.remaining = delegation.remaining.checked_add(amount).ok_or(Error::VotesOverflow)?;
+
+            let from_received = amount.min(received);
+            let passed_on = self.delegated_spend(caller, from_received);
+            self.spend_delegated(caller, &passed_on);
+            for (delegator, votes) in passed_on {
+                match delegation.passed_on.iter_mut().find(|(account, _)| *account == delegator) {
+                    Some((_, passed)) => *passed = passed.saturating_add(votes),
+                    None => delegation.passed_on.push((delegator, votes)),
+                }
+            }
+            voter.available_votes -= amount - from_received;
+            self.store_voter(&voter);
+
+            self.delegations.insert(caller, &delegation);
+            self.fund(caller, delegate);
+            self.delegated_in.insert(delegate, &delegate_received);
+
+            self.env().emit_event(Delegated { delegator: caller, delegate, amount });
+
+            Ok(())
+        }
+
+        /// Revokes the caller's delegation. Whatever the delegate has not
+        /// spent or passed on yet of it comes back: votes the caller passed
+        /// on from delegations they received go back to those, and the rest
+        /// returns to the caller's `available_votes`.
+        #[ink(message)]
+        pub fn revoke_delegation(&mut self) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Delegation)?;
+            let caller = self.env().caller();
+            let mut voter: Voter = self.load_voter(caller)?;
+            let delegation = self.delegations.get(caller).ok_or(Error::NoDelegation)?;
+
+            let refunded = self.drop_delegation(caller, &delegation);
+            let mut own = refunded;
+            let mut returned = Vec::new();
+            for &(delegator, passed) in delegation.passed_on.iter().rev() {
+                let votes = own.min(passed);
+                own -= votes;
+                returned.push((delegator, votes));
+            }
+            voter.available_votes = voter.available_votes.saturating_add(own);
+            self.store_voter(&voter);
+            for (delegator, votes) in returned {
+                self.refund_delegated(delegator, caller, votes);
+            }
+
+            self.env().emit_event(DelegationRevoked {
+                delegator: caller,
+                delegate: delegation.delegate,
+                refunded,
+            });
+
+            Ok(())
+        }
+
+        /// Configures the limits `vote` enforces against reputation pumping;
+        /// see `CollusionRules`. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn set_collusion_rules(
+            &mut self,
+            candidate_epoch_cap: Option<u128>,
+            max_candidate_share: Option<u16>,
+            reciprocal_window: BlockNumber,
+            reciprocal_policy: ReciprocalPolicy,
+        ) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.configure_collusion_rules(CollusionRules {
+                candidate_epoch_cap,
+                max_candidate_share,
+                reciprocal_window,
+                reciprocal_policy,
+            })
+        }
+
+        #[ink(message)]
+        pub fn collusion_rules(&self) -> CollusionRules {
+            self.collusion_rules.get_or_default()
+        }
+
+        /// Scales the reputation effect of every vote by a weight derived
+        /// from the voter's reputation through `curve`: `per_unit` basis
+        /// points per unit of curve output, clamped to `floor..=cap`. A
+        /// `curve` of `None` turns weighting off. The full cost is charged
+        /// either way. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn set_vote_weight_config(
+            &mut self,
+            curve: Option<WeightCurve>,
+            per_unit: u16,
+            floor: u16,
+            cap: u16,
+        ) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.configure_vote_weight(curve.map(|curve| VoteWeightConfig { curve, per_unit, floor, cap }))
+        }
+
+        #[ink(message)]
+        pub fn vote_weight_config(&self) -> Option<VoteWeightConfig> {
+            self.vote_weight_config.get_or_default()
+        }
+
+        /// Returns the weight, in basis points, applied to the reputation
+        /// effect of `voter`'s votes, or `None` if they are not registered.
+        #[ink(message)]
+        pub fn effective_vote_weight(&self, voter: AccountId) -> Option<u16> {
+            self.load_voter(voter).ok().map(|voter| self.vote_weight(&voter))
+        }
+
+        /// Sets the most entries `add_voters`, `remove_voters` and
+        /// `vote_many` accept. Requires the `Admin` role.
+        #[ink(message)]
+        pub fn set_max_batch_size(&mut self, max_batch_size: u32) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.max_batch_size.set(&max_batch_size);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_batch_size(&self) -> u32 {
+            self.max_batch_size.get().unwrap_or(DEFAULT_MAX_BATCH_SIZE)
+        }
+
+        /// Sets the longest chain of delegations, counted in hops, that
+        /// `delegate` will create.
+        #[ink(message)]
+        pub fn set_max_delegation_depth(&mut self, max_depth: u32) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.max_delegation_depth.set(&max_depth);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_delegation_depth(&self) -> u32 {
+            self.max_delegation_depth.get().unwrap_or(DEFAULT_MAX_DELEGATION_DEPTH)
+        }
+
+        #[ink(message)]
+        pub fn delegation_of(&self, account: AccountId) -> Option<Delegation> {
+            self.delegations.get(account)
+        }
+
+        /// Returns the delegated votes `account` can still spend or pass on.
+        #[ink(message)]
+        pub fn delegated_in(&self, account: AccountId) -> u128 {
+            self.delegated_in.get(account).unwrap_or(0)
+        }
+
+        /// Returns the votes `account` has delegated to someone else.
+        #[ink(message)]
+        pub fn delegated_out(&self, account: AccountId) -> u128 {
+            self.delegations.get(account).map_or(0, |delegation| delegation.amount)
+        }
+
+        /// Removes `voter_address` from the registry, freezing its
+        /// registration deposit, if any, for `seize_deposit`. Requires the
+        /// `Moderator` role.
+        #[ink(message)]
+        pub fn remove_voter(&mut self, voter_address: AccountId) -> Result<(), Error> {
+            self.ensure_role(Role::Moderator)?;
+            self.ensure_not_paused(Operation::Removal)?;
+            self.unregister_voter(voter_address)?;
+            self.freeze_deposit(voter_address);
+            Ok(())
+        }
+
+        /// Takes `amount` reputation and up to `votes` of the
+        /// `available_votes` from `account`, recording `reason_hash` as the
+        /// justification. Whoever vouched `account` in is penalised too.
+        /// Returns the index of the slash record. Requires the `Moderator`
+        /// role.
+        #[ink(message)]
+        pub fn slash(&mut self, account: AccountId, amount: Reputation, votes: u128, reason_hash: Hash) -> Result<u32, Error> {
+            self.ensure_role(Role::Moderator)?;
+            self.ensure_not_paused(Operation::Removal)?;
+            if amount < 0 || (amount == 0 && votes == 0) {
+                return Err(Error::EmptySlash);
+            }
+            let mut voter: Voter = self.load_voter(account)?;
+
+            let reputation = voter.reputation;
+            voter.reputation = reputation.checked_sub(amount).ok_or(Error::ReputationOverflow)?;
+            let categories = self.slash_categories(account, amount, reputation)?;
+            let votes = votes.min(voter.available_votes);
+            voter.available_votes -= votes;
+            self.store_voter(&voter);
+
+            let slashed_by = self.env().caller();
+            let slashed_at = self.env().block_number();
+            let index = self.slash_counts.get(account).unwrap_or(0);
+            self.env().emit_event(Slashed { account, index, amount, votes, reason_hash, slashed_by });
+            let penalties = self.penalise_vouchers(account);
+
+            self.slashes.insert((account, index), &SlashRecord {
+                amount,
+                votes,
+                reason_hash,
+                slashed_by,
+                slashed_at,
+                appeal_until: slashed_at.saturating_add(self.slash_appeal_window.get_or_default()),
+                appealed: false,
+                reversed: false,
+                categories,
+                penalties,
+            });
+            self.slash_counts.insert(account, &(index + 1));
+
+            Ok(index)
+        }
+
+        /// Appeals against the caller's slash `index` while its appeal
+        /// window is open, flagging it for review by an admin, who may then
+        /// `reverse_slash` it.
+        #[ink(message)]
+        pub fn appeal(&mut self, index: u32) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Removal)?;
+            let account = self.env().caller();
+            let mut record = self.open_slash(account, index)?;
+            if record.appealed {
+                return Err(Error::AlreadyAppealed);
+            }
+
+            record.appealed = true;
+            self.slashes.insert((account, index), &record);
+
+            self.env().emit_event(SlashAppealed { account, index });
+
+            Ok(())
+        }
+
+        /// Gives back what slash `index` took from `account` and from its
+        /// vouchers, as long as its appeal window is open. Requires the
+        /// `Admin` role.
+        #[ink(message)]
+        pub fn reverse_slash(&mut self, account: AccountId, index: u32) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.ensure_not_paused(Operation::Removal)?;
+            let mut record = self.open_slash(account, index)?;
+            let mut voter: Voter = self.load_voter(account)?;
+
+            voter.reputation = voter.reputation.checked_add(record.amount).ok_or(Error::ReputationOverflow)?;
+            voter.available_votes = voter.available_votes.checked_add(record.votes).ok_or(Error::VotesOverflow)?;
+            for &(category, taken) in &record.categories {
+                let reputation = self.reputation_in(account, category);
+                self.set_reputation_in(account, category, reputation.checked_add(taken).ok_or(Error::ReputationOverflow)?);
+            }
+            self.store_voter(&voter);
+            for &(voucher_address, penalty) in &record.penalties {
+                if let Ok(mut voucher) = self.load_voter(voucher_address) {
+                    voucher.reputation = voucher.reputation.saturating_add(penalty);
+                    self.store_voter(&voucher);
+                }
+            }
+            record.reversed = true;
+            self.slashes.insert((account, index), &record);
+
+            self.env().emit_event(SlashReversed { account, index, reversed_by: self.env().caller() });
+
+            Ok(())
+        }
+
+        /// Sets how many blocks after a slash it can still be reversed.
+        /// Slashes already made keep their window. Requires the `Admin`
+        /// role.
+        #[ink(message)]
+        pub fn set_slash_appeal_window(&mut self, blocks: BlockNumber) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.slash_appeal_window.set(&blocks);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn slash_appeal_window(&self) -> BlockNumber {
+            self.slash_appeal_window.get_or_default()
+        }
+
+        #[ink(message)]
+        pub fn slash_record(&self, account: AccountId, index: u32) -> Option<SlashRecord> {
+            self.slashes.get((account, index))
+        }
+
+        /// Returns how many slashes `account` has received, reversed ones
+        /// included.
+        #[ink(message)]
+        pub fn slash_count(&self, account: AccountId) -> u32 {
+            self.slash_counts.get(account).unwrap_or(0)
+        }
+
+        /// Opens disputes: each costs a bond of exactly `bond`, and is ruled
+        /// on by `jurors` voters within `ruling_period` blocks. Requires the
+        /// `Admin` role.
+        #[ink(message)]
+        pub fn set_dispute_config(&mut self, bond: Balance, jurors: u32, ruling_period: BlockNumber) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.configure_disputes(DisputeConfig { bond, jurors, ruling_period })
+        }
+
+        #[ink(message)]
+        pub fn dispute_config(&self) -> Option<DisputeConfig> {
+            self.dispute_config.get_or_default()
+        }
+
+        /// Disputes the logged vote `vote_id`, locking exactly the
+        /// configured bond. `seed_commitment` hides a secret of the
+        /// disputant's, as computed by `juror_seed_hash`, which they reveal
+        /// through `draw_jurors` once `JUROR_DRAW_DELAY` blocks have passed.
+        /// Returns the dispute id.
+        #[ink(message, payable)]
+        pub fn open_dispute(&mut self, vote_id: u64, seed_commitment: Hash) -> Result<u32, Error> {
+            self.ensure_not_paused(Operation::Disputes)?;
+            let config = self.dispute_config.get_or_default().ok_or(Error::DisputesClosed)?;
+            if self.env().transferred_value() != config.bond {
+                return Err(Error::WrongDeposit);
+            }
+            let disputant = self.env().caller();
+            if !self.is_voter(disputant) {
+                return Err(Error::UnregisteredVoter);
+            }
+            let entry = self.vote_log.get(vote_id).ok_or(Error::UnknownVote)?;
+            if entry.voter == disputant {
+                return Err(Error::OwnVote);
+            }
+            if self.vote_disputes.contains(vote_id) {
+                return Err(Error::VoteAlreadyDisputed);
+            }
+
+            let parties = [disputant, entry.voter, entry.candidate];
+            let ranked_parties = (0..parties.len())
+                .filter(|&index| !parties[..index].contains(&parties[index]) && self.ranking.contains(parties[index]))
+                .count() as u32;
+            if self.ranked_count.get_or_default().saturating_sub(ranked_parties) < config.jurors {
+                return Err(Error::NotEnoughJurors);
+            }
+
+            let dispute_id = self.dispute_count.get_or_default();
+            let draw_at = self.env().block_number().saturating_add(JUROR_DRAW_DELAY);
+            let deadline = draw_at.saturating_add(config.ruling_period);
+            self.disputes.insert(dispute_id, &Dispute {
+                vote_id,
+                disputant,
+                defendant: entry.voter,
+                bond: config.bond,
+                seed_commitment,
+                draw_at,
+                juror_count: config.jurors,
+                jurors: Vec::new(),
+                deadline,
+                uphold: 0,
+                reject: 0,
+                status: DisputeStatus::Open,
+            });
+            self.dispute_count.set(&(dispute_id + 1));
+            self.vote_disputes.insert(vote_id, &dispute_id);
+            self.mix_entropy();
+
+            self.env().emit_event(DisputeOpened { dispute_id, vote_id, disputant, draw_at, deadline });
+
+            Ok(dispute_id)
+        }
+
+        /// Draws the jurors of a dispute from its `draw_at` block on,
+        /// given the secret behind its seed commitment. The draw is seeded
+        /// by the secret together with the activity recorded up to the
+        /// block before `draw_at`, and spread over the ranking, skipping
+        /// the disputant, the defendant and the candidate. A dispute whose
+        /// jurors are not drawn by its deadline is rejected.
+        #[ink(message)]
+        pub fn draw_jurors(&mut self, dispute_id: u32, secret: [u8; 32]) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Disputes)?;
+            let mut dispute = self.disputes.get(dispute_id).ok_or(Error::UnknownDispute)?;
+            if dispute.status != DisputeStatus::Open {
+                return Err(Error::DisputeResolved);
+            }
+            if !dispute.jurors.is_empty() {
+                return Err(Error::JurorsAlreadyDrawn);
+            }
+            let block = self.env().block_number();
+            if block < dispute.draw_at {
+                return Err(Error::DrawNotDue);
+            }
+            if block > dispute.deadline {
+                return Err(Error::RulingClosed);
+            }
+            if dispute.seed_commitment != self.juror_seed_hash(dispute.disputant, secret) {
+                return Err(Error::InvalidReveal);
+            }
+
+            let entry = self.vote_log.get(dispute.vote_id).ok_or(Error::UnknownVote)?;
+            let parties = [dispute.disputant, entry.voter, entry.candidate];
+            let seed = self
+                .env()
+                .hash_encoded::<ink::env::hash::Blake2x256, _>(&(secret, self.entropy_at(dispute.draw_at - 1)));
+            let jurors = self.pick_jurors(seed, &parties, dispute.juror_count);
+            if jurors.is_empty() {
+                return Err(Error::NotEnoughJurors);
+            }
+            dispute.jurors = jurors.clone();
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(JurorsDrawn { dispute_id, jurors });
+
+            Ok(())
+        }
+
+        /// Returns the hash `disputant` commits to in `open_dispute` for
+        /// drawing jurors with `secret`.
+        #[ink(message)]
+        pub fn juror_seed_hash(&self, disputant: AccountId, secret: [u8; 32]) -> Hash {
+            Hash::from(self.env().hash_encoded::<ink::env::hash::Blake2x256, _>(&(disputant, secret)))
+        }
+
+        /// Records the caller's ruling on a dispute they are a juror of:
+        /// `uphold` to side with the disputant. Rulings are final.
+        #[ink(message)]
+        pub fn rule(&mut self, dispute_id: u32, uphold: bool) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Disputes)?;
+            let juror = self.env().caller();
+            let mut dispute = self.disputes.get(dispute_id).ok_or(Error::UnknownDispute)?;
+            if dispute.status != DisputeStatus::Open {
+                return Err(Error::DisputeResolved);
+            }
+            if self.env().block_number() > dispute.deadline {
+                return Err(Error::RulingClosed);
+            }
+            if !dispute.jurors.contains(&juror) {
+                return Err(Error::NotAJuror);
+            }
+            if !self.is_voter(juror) {
+                return Err(Error::UnregisteredVoter);
+            }
+            if self.rulings.contains((dispute_id, juror)) {
+                return Err(Error::AlreadyRuled);
+            }
+            self.mix_entropy();
+
+            if uphold {
+                dispute.uphold += 1;
+            } else {
+                dispute.reject += 1;
+            }
+            self.disputes.insert(dispute_id, &dispute);
+            self.rulings.insert((dispute_id, juror), &uphold);
+
+            self.env().emit_event(Ruled { dispute_id, juror, uphold });
+
+            Ok(())
+        }
+
+        /// Settles a dispute once a majority of its jurors agree or its
+        /// deadline has passed. An upheld dispute reverses what is left of
+        /// the vote's reputation effect and returns the bond; a rejected
+        /// one pays the bond to the defendant. Anyone may call this.
+        #[ink(message)]
+        pub fn resolve_dispute(&mut self, dispute_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Disputes)?;
+            let mut dispute = self.disputes.get(dispute_id).ok_or(Error::UnknownDispute)?;
+            if dispute.status != DisputeStatus::Open {
+                return Err(Error::DisputeResolved);
+            }
+            let upheld = dispute.outcome(self.env().block_number()).ok_or(Error::RulingOpen)?;
+
+            let recipient = if upheld {
+                let entry = self.vote_log.get(dispute.vote_id).ok_or(Error::UnknownVote)?;
+                self.reverse_vote(&entry)?;
+                dispute.status = DisputeStatus::Upheld;
+                dispute.disputant
+            } else {
+                dispute.status = DisputeStatus::Rejected;
+                dispute.defendant
+            };
+            self.disputes.insert(dispute_id, &dispute);
+            self.env().transfer(recipient, dispute.bond).map_err(|_| Error::TransferFailed)?;
+
+            self.env().emit_event(DisputeResolved { dispute_id, status: dispute.status });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn vote_entry(&self, vote_id: u64) -> Option<VoteEntry> {
+            self.vote_log.get(vote_id)
+        }
+
+        #[ink(message)]
+        pub fn dispute(&self, dispute_id: u32) -> Option<Dispute> {
+            self.disputes.get(dispute_id)
+        }
+
+        /// Returns every registered voter. Prefer `get_voters_page` for
+        /// large registries, as this loads the whole registry in one call.
+        #[ink(message)]
+        pub fn get_voters(&self) -> Result<Vec<Voter>, Error> {
+            self.voters_in(0, self.voter_count.get_or_default())
+        }
+
+        /// Returns up to `limit` voters starting at registry position
+        /// `offset`. `limit` is capped at `MAX_PAGE_SIZE`.
+        #[ink(message)]
+        pub fn get_voters_page(&self, offset: u32, limit: u32) -> Result<Vec<Voter>, Error> {
+            self.voters_in(offset, limit.min(MAX_PAGE_SIZE))
+        }
+
+        /// Returns up to `limit` of the candidates `voter` has voted for,
+        /// starting at position `offset`, with the ledger entry for each
+        /// pair. `limit` is capped at `MAX_PAGE_SIZE`; lapsed entries are
+        /// skipped, so a page can come back short.
+        #[ink(message)]
+        pub fn votes_given(&self, voter: AccountId, offset: u32, limit: u32) -> Vec<(AccountId, VoteRecord)> {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.candidates_count.get(voter).unwrap_or(0));
+            (offset..end)
+                .filter_map(|index| self.candidates_of.get((voter, index)))
+                .filter_map(|candidate| Some((candidate, self.vote_record(voter, candidate)?)))
+                .collect()
+        }
+
+        /// Returns up to `limit` of the voters that have voted for
+        /// `candidate`, starting at position `offset`, with the ledger entry
+        /// for each pair. Paged like `votes_given`.
+        #[ink(message)]
+        pub fn votes_received(&self, candidate: AccountId, offset: u32, limit: u32) -> Vec<(AccountId, VoteRecord)> {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.supporters_count.get(candidate).unwrap_or(0));
+            (offset..end)
+                .filter_map(|index| self.supporters_of.get((candidate, index)))
+                .filter_map(|voter| Some((voter, self.vote_record(voter, candidate)?)))
+                .collect()
+        }
+
+        /// Returns the ledger entry for votes cast by `voter` on `candidate`.
+        #[ink(message)]
+        pub fn vote_between(&self, voter: AccountId, candidate: AccountId) -> Option<VoteRecord> {
+            self.vote_record(voter, candidate)
+        }
+
+        /// Starts voting epochs of `length` blocks from the current block.
+        /// At the start of every epoch each voter's `available_votes` is
+        /// refilled with `allowance` according to `mode`. A `length` of zero
+        /// disables epochs.
+        #[ink(message)]
+        pub fn set_epoch_config(&mut self, length: BlockNumber, allowance: u128, mode: RefillMode) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.configure_epochs(length, allowance, mode);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn epoch_config(&self) -> Option<EpochConfig> {
+            self.epoch_config.get_or_default()
+        }
+
+        /// Returns the index of the current epoch and the block at which the
+        /// next one starts, or `None` if epochs are disabled.
+        #[ink(message)]
+        pub fn current_epoch(&self) -> Option<(BlockNumber, BlockNumber)> {
+            let config = self.epoch_config.get_or_default()?;
+            let block = self.env().block_number();
+            Some((config.epoch_of(block), config.epoch_start(block) + config.length))
+        }
+
+        /// Makes reputation decay exponentially, halving every `half_life`
+        /// milliseconds from now on. A `half_life` of zero disables decay;
+        /// reputation keeps whatever it decayed by until then.
+        #[ink(message)]
+        pub fn set_reputation_half_life(&mut self, half_life: Timestamp) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.configure_decay(half_life);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn decay_config(&self) -> Option<DecayConfig> {
+            self.decay_config.get_or_default()
+        }
+
+        /// Returns how many `available_votes` `voter` would spend casting
+        /// `votes` on `candidate` under the configured cost model.
+        #[ink(message)]
+        pub fn vote_cost(&self, voter: AccountId, candidate: AccountId, votes: i128) -> Result<u128, Error> {
+            let already_cast = self.vote_record(voter, candidate).unwrap_or_default().total_cast;
+            self.cost_model.get_or_default().cost(already_cast, votes.unsigned_abs())
+        }
+
+        #[ink(message)]
+        pub fn cost_model(&self) -> CostModel {
+            self.cost_model.get_or_default()
+        }
+
+        #[ink(message)]
+        pub fn voter_count(&self) -> u32 {
+            self.voter_count.get_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_voter(&self, account: AccountId) -> Option<Voter> {
+            self.load_voter(account).ok()
+        }
+
+        /// Returns up to `n` voters with the highest reputation, best first.
+        /// `n` is capped at `MAX_PAGE_SIZE`. Voters with equal reputation
+        /// are ordered by account.
+        #[ink(message)]
+        pub fn top(&self, n: u32) -> Vec<Voter> {
+            self.walk_ranking(self.rank_link(None, 0).next, n, |contract, account| {
+                contract.rank_link(Some(account), 0).next
+            })
+        }
+
+        /// Returns up to `n` voters with the lowest reputation, worst first.
+        /// `n` is capped at `MAX_PAGE_SIZE`.
+        #[ink(message)]
+        pub fn bottom(&self, n: u32) -> Vec<Voter> {
+            self.walk_ranking(self.ranking_tail.get_or_default(), n, |contract, account| {
+                contract.ranking.get(account).and_then(|node| node.prev)
+            })
+        }
+
+        /// Returns the 1-based position of `account` in the ranking.
+        #[ink(message)]
+        pub fn rank_of(&self, account: AccountId) -> Option<u32> {
+            let node = self.ranking.get(account)?;
+            let (_, position) = self.rank_search(|next, next_node| ranks_before(next_node.key, next, node.key, account))[0];
+            Some(position + 1)
+        }
+
+        /// Returns `account`'s reputation at the end of `block`, or zero if
+        /// it was not a voter then. Only past blocks are accepted, so votes
+        /// cast in the current block cannot move the answer. Reputation is
+        /// decayed up to the decay clock of the contract's last reputation
+        /// change at or before `block`, as block timestamps are not kept.
+        #[ink(message)]
+        pub fn reputation_at(&self, account: AccountId, block: BlockNumber) -> Result<Reputation, Error> {
+            let clock = self.checkpoint_at(None, block)?.map_or(0, |total| total.decay_clock);
+            self.decayed_reputation_at(account, block, clock)
+        }
+
+        /// Returns `total_reputation` as it stood at the end of `block`,
+        /// which must be in the past and, after an upgrade from version 1,
+        /// no earlier than the block `migrate` finished in. Decayed like
+        /// `reputation_at`.
+        #[ink(message)]
+        pub fn total_reputation_at(&self, block: BlockNumber) -> Result<Reputation, Error> {
+            if self.storage_version() < STORAGE_VERSION || self.migrated_at.get().is_some_and(|migrated_at| block < migrated_at) {
+                return Err(Error::MigrationPending);
+            }
+            Ok(self.checkpoint_at(None, block)?.map_or(0, |total| total.reputation))
+        }
+
+        /// Returns the sum of every voter's decayed reputation, saturating
+        /// at the bounds of `Reputation`. Version 1 voters only count once
+        /// converted.
+        #[ink(message)]
+        pub fn total_reputation(&self) -> Reputation {
+            self.decayed(self.total_reputation.get_or_default(), Some(self.total_decay_clock.get_or_default()))
+        }
+
+        /// Opens governance: proposals take ballots for `voting_period`
+        /// blocks and pass with at least `quorum` total weight and a yes
+        /// share of at least `threshold` basis points of yes and no.
+        /// Requires the `Admin` role.
+        #[ink(message)]
+        pub fn set_governance_config(&mut self, voting_period: BlockNumber, quorum: Reputation, threshold: u16) -> Result<(), Error> {
+            self.ensure_role(Role::Admin)?;
+            self.configure_governance(GovernanceConfig { voting_period, quorum, threshold })
+        }
+
+        #[ink(message)]
+        pub fn governance_config(&self) -> Option<GovernanceConfig> {
+            self.governance_config.get_or_default()
+        }
+
+        /// Submits a proposal described off-chain by `description_hash`,
+        /// optionally carrying an encoded `call` to one of the `set_*` admin
+        /// messages it mirrors in `ProposalAction`, which is made once the
+        /// proposal passes and is executed. Its snapshot is the previous
+        /// block and voting opens with the next one. The caller must be a
+        /// voter. Returns the proposal id.
+        #[ink(message)]
+        pub fn propose(&mut self, description_hash: Hash, call: Option<Vec<u8>>) -> Result<u32, Error> {
+            self.ensure_not_paused(Operation::Governance)?;
+            let config = self.governance_config.get_or_default().ok_or(Error::GovernanceClosed)?;
+            let proposer = self.env().caller();
+            if !self.is_voter(proposer) {
+                return Err(Error::UnregisteredVoter);
+            }
+            let action = call.as_deref().map(ProposalAction::from_call).transpose()?;
+            if action.as_ref().is_some_and(|action| !action.is_valid()) {
+                return Err(Error::InvalidConfig);
+            }
+
+            let proposal_id = self.proposal_count.get_or_default();
+            let block = self.env().block_number();
+            let ends_at = block.saturating_add(config.voting_period);
+            self.proposals.insert(proposal_id, &Proposal {
+                proposer,
+                description_hash,
+                action,
+                snapshot: block.saturating_sub(1),
+                decay_clock: self.decay_clock(),
+                starts_at: block.saturating_add(1),
+                ends_at,
+                quorum: config.quorum,
+                threshold: config.threshold,
+                yes: 0,
+                no: 0,
+                abstain: 0,
+                executed: false,
+            });
+            self.proposal_count.set(&(proposal_id + 1));
+
+            self.env().emit_event(ProposalCreated { proposal_id, proposer, description_hash, ends_at });
+
+            Ok(proposal_id)
+        }
+
+        /// Casts the caller's `ballot` on an active proposal, weighted by
+        /// their reputation at its snapshot, decayed up to when the proposal
+        /// was made. Ballots are final.
+        #[ink(message)]
+        pub fn cast_ballot(&mut self, proposal_id: u32, ballot: Ballot) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Governance)?;
+            let voter = self.env().caller();
+            let mut proposal = self.proposals.get(proposal_id).ok_or(Error::UnknownProposal)?;
+            if proposal.state(self.env().block_number()) != ProposalState::Active {
+                return Err(Error::VotingNotOpen);
+            }
+            if self.ballots.contains((proposal_id, voter)) {
+                return Err(Error::AlreadyVoted);
+            }
+            let weight = self.decayed_reputation_at(voter, proposal.snapshot, proposal.decay_clock)?;
+            if weight <= 0 {
+                return Err(Error::NoVotingWeight);
+            }
+            self.mix_entropy();
+
+            let tally = match ballot {
+                Ballot::Yes => &mut proposal.yes,
+                Ballot::No => &mut proposal.no,
+                Ballot::Abstain => &mut proposal.abstain,
+            };
+            *tally = tally.saturating_add(weight);
+            self.proposals.insert(proposal_id, &proposal);
+            self.ballots.insert((proposal_id, voter), &ballot);
+
+            self.env().emit_event(BallotCast { proposal_id, voter, ballot, weight });
+
+            Ok(())
+        }
+
+        /// Applies the action of a proposal that passed once its voting
+        /// period is over. Anyone may call this, once per proposal.
+        #[ink(message)]
+        pub fn execute(&mut self, proposal_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused(Operation::Governance)?;
+            let mut proposal = self.proposals.get(proposal_id).ok_or(Error::UnknownProposal)?;
+            match proposal.state(self.env().block_number()) {
+                ProposalState::Pending | ProposalState::Active => return Err(Error::VotingNotEnded),
+                ProposalState::Defeated => return Err(Error::ProposalDefeated),
+                ProposalState::Executed => return Err(Error::AlreadyExecuted),
+                ProposalState::Succeeded => {}
+            }
+
+            match proposal.action.clone() {
+                Some(ProposalAction::MaxBatchSize(max_batch_size)) => self.max_batch_size.set(&max_batch_size),
+                Some(ProposalAction::MaxDelegationDepth(max_depth)) => self.max_delegation_depth.set(&max_depth),
+                Some(ProposalAction::EpochConfig { length, allowance, mode }) => self.configure_epochs(length, allowance, mode),
+                Some(ProposalAction::ReputationHalfLife(half_life)) => self.configure_decay(half_life),
+                Some(ProposalAction::GovernanceConfig(config)) => self.configure_governance(config)?,
+                Some(ProposalAction::CollusionRules(rules)) => self.configure_collusion_rules(rules)?,
+                Some(ProposalAction::VoteWeightConfig(config)) => self.configure_vote_weight(config)?,
+                Some(ProposalAction::SlashAppealWindow(blocks)) => self.slash_appeal_window.set(&blocks),
+                Some(ProposalAction::DisputeConfig(config)) => self.configure_disputes(config)?,
+                None => {}
+            }
+            proposal.executed = true;
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(ProposalExecuted { proposal_id });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn proposal(&self, proposal_id: u32) -> Option<Proposal> {
+            self.proposals.get(proposal_id)
+        }
+
+        #[ink(message)]
+        pub fn proposal_state(&self, proposal_id: u32) -> Option<ProposalState> {
+            self.proposals.get(proposal_id).map(|proposal| proposal.state(self.env().block_number()))
+        }
+
+        #[ink(message)]
+        pub fn proposal_count(&self) -> u32 {
+            self.proposal_count.get_or_default()
+        }
+
+        #[ink(message)]
+        pub fn ballot_of(&self, proposal_id: u32, voter: AccountId) -> Option<Ballot> {
+            self.ballots.get((proposal_id, voter))
+        }
+
+        /// Returns up to `limit` voters whose reputation lies in
+        /// `min..=max`, best first, skipping the first `offset` of them.
+        /// `limit` is capped at `MAX_PAGE_SIZE`.
+        #[ink(message)]
+        pub fn voters_in_range(&self, min: Reputation, max: Reputation, offset: u32, limit: u32) -> Vec<Voter> {
+            let clock = self.decay_clock();
+            let (low, high) = (rank_key(min, clock), rank_key(max, clock));
+            let (_, above) = self.rank_search(|_, node| node.key > high)[0];
+
+            let mut voters: Vec<Voter> = Vec::new();
+            let mut cursor = self.ranked_at(above.saturating_add(offset).saturating_add(1));
+            while let Some(current) = cursor {
+                if voters.len() as u32 >= limit.min(MAX_PAGE_SIZE)
+                    || self.ranking.get(current).is_none_or(|node| node.key < low)
+                {
+                    break
+                }
+                voters.extend(self.load_voter(current).ok());
+                cursor = self.rank_link(Some(current), 0).next;
+            }
+            voters
+        }
+    }
+
+    #[ink(impl)]
+    impl OptionsAndFutures {
+        fn cast_vote(
+            &mut self,
+            voter_address: AccountId,
+            candidate_address: AccountId,
+            votes: i128,
+            category: Option<CategoryId>,
+        ) -> Result<(), Error> {
+            if votes == 0 {
+                return Err(Error::ZeroVotes);
+            }
+            if category.is_some_and(|category| !self.categories.contains(category)) {
+                return Err(Error::UnknownCategory);
+            }
+            self.mix_entropy();
+            let mut voter: Voter = self.load_voter(voter_address)?;
+
+            let cost = self.vote_cost(voter_address, candidate_address, votes)?;
+            let delegated = self.delegated_in.get(voter_address).unwrap_or(0);
+            if voter.available_votes.saturating_add(delegated) < cost {
+                return Err(Error::InsufficientVotes);
+            }
+
+            if candidate_address == voter.address {
+                return Err(Error::VoterEqualToCandidate);
+            }
+
+            let mut candidate: Voter = self.load_voter(candidate_address)?;
+
+            let budget = voter.available_votes.saturating_add(delegated);
+            let (effect, pair_tally, voter_tally) =
+                self.apply_collusion_rules(voter_address, candidate_address, votes, cost, budget)?;
+            let effect = scale_basis_points(effect, self.vote_weight(&voter).into());
+
+            candidate.reputation = candidate.reputation.checked_add(effect).ok_or(Error::ReputationOverflow)?;
+            let category_reputation = match category {
+                Some(category) => {
+                    let reputation = self.reputation_in(candidate_address, category);
+                    Some((category, reputation.checked_add(effect).ok_or(Error::ReputationOverflow)?))
+                }
+                None => None,
+            };
+            // Spend the voter's own votes before delegated ones.
+            let from_delegated = cost.saturating_sub(voter.available_votes);
+            let delegated_spend = self.delegated_spend(voter_address, from_delegated);
+
+            // Checked before anything is written, so a failure leaves no
+            // partial update behind.
+            self.record_vote(voter_address, candidate_address, votes, effect, category, &delegated_spend)?;
+            if let Some((category, reputation)) = category_reputation {
+                self.set_reputation_in(candidate_address, category, reputation);
+            }
+            self.pair_tallies.insert((voter_address, candidate_address), &pair_tally);
+            self.voter_tallies.insert(voter_address, &voter_tally);
+
+            voter.available_votes -= cost - from_delegated;
+            self.spend_delegated(voter_address, &delegated_spend);
+
+            self.store_voter(&candidate);
+            self.store_voter(&voter);
+
+            let vote_id = self.vote_log_count.get_or_default();
+            self.vote_log.insert(vote_id, &VoteEntry {
+                voter: voter_address,
+                candidate: candidate_address,
+                votes,
+                effect,
+                category,
+                block: self.env().block_number(),
+                decay_clock: self.decay_clock(),
+            });
+            self.vote_log_count.set(&(vote_id + 1));
+
+            self.env().emit_event(VoteCast {
+                voter: voter_address,
+                candidate: candidate_address,
+                votes,
+                reputation: candidate.reputation,
+                vote_id,
+                category,
+            });
+
+            Ok(())
+        }
+
+        /// Reverses `amount` of the net votes `voter_address` has cast on
+        /// `candidate_address` and returns the signed votes that were taken
+        /// back.
+        fn retract(&mut self, voter_address: AccountId, candidate_address: AccountId, amount: u128) -> Result<i128, Error> {
+            if amount == 0 {
+                return Err(Error::ZeroVotes);
+            }
+            let mut voter: Voter = self.load_voter(voter_address)?;
+            let mut candidate: Voter = self.load_voter(candidate_address)?;
+            let mut record = self.vote_record(voter_address, candidate_address).ok_or(Error::RetractExceedsVotes)?;
+
+            if amount > record.net.unsigned_abs() {
+                return Err(Error::RetractExceedsVotes);
+            }
+            // `amount` is bounded by `|net|`, so it keeps the sign of `net`
+            // without overflowing, `i128::MIN` included.
+            let votes = if record.net < 0 {
+                0i128.checked_sub_unsigned(amount)
+            } else {
+                0i128.checked_add_unsigned(amount)
+            }
+            .ok_or(Error::VotesOverflow)?;
+            // Refund what the retracted votes from this epoch cost when they
+            // were the most recent ones cast on this candidate, and take back
+            // the share of the effect of all of them.
+            let refunded = amount.min(record.refundable);
+            let refund = self.cost_model.get_or_default().cost(record.total_cast - refunded, refunded)?;
+            let reversal = proportion(record.effect, amount, record.net.unsigned_abs())?;
+            let mut category_reputation = Vec::new();
+            for (category, share) in record.categories.iter_mut() {
+                let share_reversal = proportion(*share, amount, record.net.unsigned_abs())?;
+                let reputation = self.reputation_in(candidate_address, *category);
+                category_reputation.push((*category, reputation.checked_sub(share_reversal).ok_or(Error::ReputationOverflow)?));
+                *share -= share_reversal;
+            }
+
+            // Votes delegations paid for go back to them first, the most
+            // recent first.
+            let mut own = refund;
+            let mut returned = Vec::new();
+            while own > 0 {
+                let Some((delegator, paid)) = record.delegated.last_mut() else {
+                    break
+                };
+                let votes = own.min(*paid);
+                own -= votes;
+                *paid -= votes;
+                returned.push((*delegator, votes));
+                if *paid == 0 {
+                    record.delegated.pop();
+                }
+            }
+
+            candidate.reputation = candidate.reputation.checked_sub(reversal).ok_or(Error::ReputationOverflow)?;
+            voter.available_votes = voter.available_votes.checked_add(own).ok_or(Error::VotesOverflow)?;
+            record.net -= votes;
+            record.total_cast -= amount;
+            record.effect -= reversal;
+            record.refundable -= refunded;
+            record.last_block = self.env().block_number();
+            record.categories.retain(|&(_, share)| share != 0);
+
+            for (category, reputation) in category_reputation {
+                self.set_reputation_in(candidate_address, category, reputation);
+            }
+            self.store_voter(&candidate);
+            self.store_voter(&voter);
+            self.vote_records.insert((voter_address, candidate_address), &record);
+            for (delegator, votes) in returned {
+                self.refund_delegated(delegator, voter_address, votes);
+            }
+
+            self.env().emit_event(VoteRetracted {
+                voter: voter_address,
+                candidate: candidate_address,
+                votes,
+                reputation: candidate.reputation,
+            });
+
+            Ok(votes)
+        }
+
+        /// First block of the current epoch, or zero without epochs.
+        fn epoch_start(&self) -> BlockNumber {
+            self.epoch_config.get_or_default().map_or(0, |config| config.epoch_start(self.env().block_number()))
+        }
+
+        /// Reads a ledger entry as of now: with its effect decayed, and
+        /// nothing refundable left from earlier epochs. Entries lapse once
+        /// the voter or the candidate is removed.
+        fn vote_record(&self, voter: AccountId, candidate: AccountId) -> Option<VoteRecord> {
+            self.candidate_record(voter, candidate)
+                .filter(|record| record.removals.0 == self.removals.get(voter).unwrap_or(0))
+        }
+
+        /// Like `vote_record`, but keeps the entries of voters removed since,
+        /// whose votes still count towards the candidate.
+        fn candidate_record(&self, voter: AccountId, candidate: AccountId) -> Option<VoteRecord> {
+            let mut record = self.vote_records.get((voter, candidate))?;
+            if record.removals.1 != self.removals.get(candidate).unwrap_or(0) {
+                return None
+            }
+            record.effect = self.decayed(record.effect, Some(record.decay_clock));
+            for (_, share) in record.categories.iter_mut() {
+                *share = self.decayed(*share, Some(record.decay_clock));
+            }
+            record.decay_clock = self.decay_clock();
+            let epoch = self.epoch_start();
+            if record.epoch != epoch {
+                record.epoch = epoch;
+                record.refundable = 0;
+                record.delegated = Vec::new();
+            }
+            Some(record)
+        }
+
+        fn vote_weight(&self, voter: &Voter) -> u16 {
+            self.vote_weight_config.get_or_default()
+                .as_ref()
+                .map_or(BASIS_POINTS, |config| config.weight(voter.reputation))
+        }
+
+        /// Takes back a logged vote's effect on the candidate, limited to
+        /// what the voter has not retracted from the pair since. The votes
+        /// leave the ledger too, so their cost cannot be refunded.
+        fn reverse_vote(&mut self, entry: &VoteEntry) -> Result<(), Error> {
+            let Some(mut record) = self.candidate_record(entry.voter, entry.candidate) else {
+                return Ok(())
+            };
+            let same_sign = |value: i128, bound: i128| if value > 0 { value.min(bound.max(0)) } else { value.max(bound.min(0)) };
+            let reversal = same_sign(self.decayed(entry.effect, Some(entry.decay_clock)), record.effect);
+            let votes = same_sign(entry.votes, record.net);
+            record.effect -= reversal;
+            record.net -= votes;
+            record.total_cast -= votes.unsigned_abs();
+            record.refundable = record.refundable.min(record.total_cast);
+            let category_reversal = entry.category.and_then(|category| {
+                let (_, share) = record.categories.iter_mut().find(|(id, _)| *id == category)?;
+                let share_reversal = same_sign(reversal, *share);
+                *share -= share_reversal;
+                Some((category, share_reversal))
+            });
+            record.categories.retain(|&(_, share)| share != 0);
+
+            if let Ok(mut candidate) = self.load_voter(entry.candidate) {
+                candidate.reputation = candidate.reputation.checked_sub(reversal).ok_or(Error::ReputationOverflow)?;
+                if let Some((category, share_reversal)) = category_reversal {
+                    let reputation = self.reputation_in(entry.candidate, category);
+                    let reputation = reputation.checked_sub(share_reversal).ok_or(Error::ReputationOverflow)?;
+                    self.set_reputation_in(entry.candidate, category, reputation);
+                }
+                self.store_voter(&candidate);
+            }
+            self.vote_records.insert((entry.voter, entry.candidate), &record);
+
+            Ok(())
+        }
+
+        /// Adds `votes` that moved the candidate's reputation by `effect` to
+        /// the `(voter, candidate)` ledger entry, crediting `category` with
+        /// the effect and noting what each delegation paid, and indexes the
+        /// pair on first use.
+        fn record_vote(
+            &mut self,
+            voter: AccountId,
+            candidate: AccountId,
+            votes: i128,
+            effect: Reputation,
+            category: Option<CategoryId>,
+            delegated_spend: &[(AccountId, u128)],
+        ) -> Result<(), Error> {
+            let indexed = self.vote_records.contains((voter, candidate));
+            let mut previous = self.vote_record(voter, candidate).unwrap_or_default();
+            for &(delegator, votes) in delegated_spend {
+                match previous.delegated.iter_mut().find(|(account, _)| *account == delegator) {
+                    Some((_, paid)) => *paid = paid.checked_add(votes).ok_or(Error::VotesOverflow)?,
+                    None => previous.delegated.push((delegator, votes)),
+                }
+            }
+            if let Some(category) = category {
+                match previous.categories.iter_mut().find(|(id, _)| *id == category) {
+                    Some((_, share)) => *share = share.checked_add(effect).ok_or(Error::ReputationOverflow)?,
+                    None => previous.categories.push((category, effect)),
+                }
+            }
+            let record = VoteRecord {
+                net: previous.net.checked_add(votes).ok_or(Error::VotesOverflow)?,
+                total_cast: previous.total_cast.checked_add(votes.unsigned_abs()).ok_or(Error::VotesOverflow)?,
+                effect: previous.effect.checked_add(effect).ok_or(Error::ReputationOverflow)?,
+                decay_clock: self.decay_clock(),
+                epoch: self.epoch_start(),
+                refundable: previous.refundable.checked_add(votes.unsigned_abs()).ok_or(Error::VotesOverflow)?,
+                removals: (self.removals.get(voter).unwrap_or(0), self.removals.get(candidate).unwrap_or(0)),
+                last_block: self.env().block_number(),
+                delegated: previous.delegated,
+                categories: previous.categories,
+            };
+
+            if !indexed {
+                let given = self.candidates_count.get(voter).unwrap_or(0);
+                self.candidates_of.insert((voter, given), &candidate);
+                self.candidates_count.insert(voter, &(given + 1));
+
+                let received = self.supporters_count.get(candidate).unwrap_or(0);
+                self.supporters_of.insert((candidate, received), &voter);
+                self.supporters_count.insert(candidate, &(received + 1));
+            }
+            self.vote_records.insert((voter, candidate), &record);
+
+            Ok(())
+        }
+
+        /// Checks `votes` costing `cost` against the collusion rules, given
+        /// the voter can still spend `budget`. Returns the reputation effect
+        /// of the vote and the updated epoch tallies for the pair and the
+        /// voter.
+        fn apply_collusion_rules(
+            &self,
+            voter: AccountId,
+            candidate: AccountId,
+            votes: i128,
+            cost: u128,
+            budget: u128,
+        ) -> Result<(i128, EpochTally, EpochTally), Error> {
+            let rules = self.collusion_rules.get_or_default();
+            let epoch = self.epoch_start();
+            let current = |tally: Option<EpochTally>| {
+                tally.filter(|tally| tally.epoch == epoch).unwrap_or(EpochTally { epoch, ..Default::default() })
+            };
+
+            let mut pair_tally = current(self.pair_tallies.get((voter, candidate)));
+            let mut voter_tally = current(self.voter_tallies.get(voter));
+            let epoch_budget = voter_tally.credits.saturating_add(budget);
+            pair_tally.votes = pair_tally.votes.saturating_add(votes.unsigned_abs());
+            pair_tally.credits = pair_tally.credits.saturating_add(cost);
+            voter_tally.credits = voter_tally.credits.saturating_add(cost);
+
+            if rules.candidate_epoch_cap.is_some_and(|cap| pair_tally.votes > cap) {
+                return Err(Error::CandidateEpochCapExceeded);
+            }
+            if let Some(share) = rules.max_candidate_share {
+                let allowed = epoch_budget.saturating_mul(share.into()) / u128::from(BASIS_POINTS);
+                if pair_tally.credits > allowed {
+                    return Err(Error::CandidateShareExceeded);
+                }
+            }
+
+            let block = self.env().block_number();
+            let reciprocal = votes > 0
+                && self.vote_record(candidate, voter).is_some_and(|record| {
+                    record.net > 0 && block <= record.last_block.saturating_add(rules.reciprocal_window)
+                });
+            let effect = match rules.reciprocal_policy {
+                ReciprocalPolicy::Block if reciprocal => return Err(Error::ReciprocalVoteBlocked),
+                ReciprocalPolicy::Discount(kept) if reciprocal => scale_basis_points(votes, kept.into()),
+                _ => votes,
+            };
+
+            Ok((effect, pair_tally, voter_tally))
+        }
+
+        fn register_voter(&mut self, voter: AccountId, available_votes: u128) -> Result<(), Error> {
+            if self.is_voter(voter) {
+                return Err(Error::VoterAlreadyRegistered);
+            }
+
+            self.index_voter(voter);
+            self.store_voter(&Voter {
+                reputation: 0,
+                address: voter,
+                available_votes,
+                registered_at: Some(self.env().block_number()),
+            });
+            self.applications.remove(voter);
+
+            self.env().emit_event(VoterAdded { voter, available_votes });
+
+            Ok(())
+        }
+
+        /// Keeps a removed voter's deposit from being refunded.
+        fn freeze_deposit(&mut self, account: AccountId) {
+            if let Some(mut deposit) = self.deposits.get(account) {
+                deposit.frozen = true;
+                self.deposits.insert(account, &deposit);
+            }
+        }
+
+        fn unregister_voter(&mut self, voter_address: AccountId) -> Result<(), Error> {
+            self.convert_voter(voter_address);
+            let index = self.voter_indices.get(voter_address).ok_or(Error::UnregisteredVoter)?;
+
+            // Swap-remove: move the last voter into the freed slot so the
+            // index stays dense.
+            let last_index = self.voter_count.get_or_default() - 1;
+            if index != last_index {
+                let last_voter = self.voters_by_index.get(last_index).ok_or(Error::UnregisteredVoter)?;
+                self.voters_by_index.insert(index, &last_voter);
+                self.voter_indices.insert(last_voter, &index);
+            }
+            self.voters_by_index.remove(last_index);
+            self.voter_indices.remove(voter_address);
+            self.voter_count.set(&last_index);
+            if let Some(voter) = self.voters_v2.take(voter_address) {
+                let reputation = self.decayed(voter.reputation, self.reputation_clocks.get(voter_address));
+                self.record_reputation(voter_address, reputation, 0);
+            }
+            self.unrank(voter_address);
+            self.refilled_at.remove(voter_address);
+            self.reputation_clocks.remove(voter_address);
+            // Lapse the account's ledger entries, as voter and as candidate.
+            let removals = self.removals.get(voter_address).unwrap_or(0);
+            self.removals.insert(voter_address, &(removals + 1));
+            for category in 0..self.category_count.get_or_default() {
+                self.category_reputation.remove((voter_address, category));
+            }
+            if let Some(delegation) = self.delegations.get(voter_address) {
+                self.drop_delegation(voter_address, &delegation);
+            }
+            // Delegations to the account forfeit what it had not spent.
+            for index in 0..self.funding_count.get(voter_address).unwrap_or(0) {
+                let Some(delegator) = self.funding.take((voter_address, index)) else {
+                    continue
+                };
+                if let Some(mut delegation) = self.delegations.get(delegator) {
+                    delegation.remaining = 0;
+                    self.delegations.insert(delegator, &delegation);
+                }
+                self.funding_index.remove(delegator);
+            }
+            self.funding_count.remove(voter_address);
+            self.delegated_in.remove(voter_address);
+            self.penalise_vouchers(voter_address);
+            self.vouchers.remove(voter_address);
+            self.vouch_penalties.remove(voter_address);
+
+            self.env().emit_event(VoterRemoved { voter: voter_address });
+
+            Ok(())
+        }
+
+        /// Takes the penalty in force when `member` was vouched in from
+        /// whoever vouched for them and returns what each of them lost.
+        fn penalise_vouchers(&mut self, member: AccountId) -> Vec<(AccountId, Reputation)> {
+            let mut penalties = Vec::new();
+            let Some(penalty) = self.vouch_penalties.get(member) else {
+                return penalties
+            };
+            for voucher_address in self.vouchers.get(member).unwrap_or_default() {
+                let Ok(mut voucher) = self.load_voter(voucher_address) else {
+                    continue
+                };
+                let reputation = voucher.reputation;
+                voucher.reputation = reputation.saturating_sub(penalty);
+                penalties.push((voucher_address, reputation - voucher.reputation));
+                self.store_voter(&voucher);
+
+                self.env().emit_event(VoucherPenalised {
+                    voucher: voucher_address,
+                    member,
+                    penalty,
+                    reputation: voucher.reputation,
+                });
+            }
+            penalties
+        }
+
+        /// Loads slash `index` of `account` if it can still be appealed and
+        /// reversed.
+        fn open_slash(&self, account: AccountId, index: u32) -> Result<SlashRecord, Error> {
+            let record = self.slashes.get((account, index)).ok_or(Error::UnknownSlash)?;
+            if record.reversed {
+                return Err(Error::SlashAlreadyReversed);
+            }
+            if self.env().block_number() > record.appeal_until {
+                return Err(Error::AppealWindowClosed);
+            }
+            Ok(record)
+        }
+
+        fn ensure_batch_size(&self, len: usize) -> Result<(), Error> {
+            if len > self.max_batch_size() as usize {
+                return Err(Error::BatchTooLarge);
+            }
+            Ok(())
+        }
+
+        /// Checks that the caller is the owner and returns it.
+        fn ensure_owner(&self) -> Result<AccountId, Error> {
+            let caller = self.env().caller();
+            if self.owner() != Some(caller) {
+                return Err(Error::OnlyOwnerFunction);
+            }
+            Ok(caller)
+        }
+
+        fn configure_epochs(&mut self, length: BlockNumber, allowance: u128, mode: RefillMode) {
+            self.epoch_config.set(&(length > 0).then(|| EpochConfig {
+                start: self.env().block_number(),
+                length,
+                allowance,
+                mode,
+            }));
+            self.env().emit_event(EpochConfigured { config: self.epoch_config.get_or_default() });
+        }
+
+        fn configure_decay(&mut self, half_life: Timestamp) {
+            // Bank the decay so far, so reputation not written since keeps
+            // it under the new configuration.
+            self.decay_halvings.set(&self.decay_clock());
+            self.decay_config.set(&(half_life > 0).then(|| DecayConfig {
+                since: self.env().block_timestamp(),
+                half_life,
+            }));
+            self.env().emit_event(DecayConfigured { config: self.decay_config.get_or_default() });
+        }
+
+        fn configure_governance(&mut self, config: GovernanceConfig) -> Result<(), Error> {
+            if !config.is_valid() {
+                return Err(Error::InvalidConfig);
+            }
+            self.governance_config.set(&Some(config));
+            Ok(())
+        }
+
+        fn configure_collusion_rules(&mut self, rules: CollusionRules) -> Result<(), Error> {
+            if !rules.is_valid() {
+                return Err(Error::InvalidConfig);
+            }
+            self.collusion_rules.set(&rules);
+            Ok(())
+        }
+
+        fn configure_vote_weight(&mut self, config: Option<VoteWeightConfig>) -> Result<(), Error> {
+            if config.as_ref().is_some_and(|config| !config.is_valid()) {
+                return Err(Error::InvalidConfig);
+            }
+            self.vote_weight_config.set(&config);
+            Ok(())
+        }
+
+        fn configure_disputes(&mut self, config: DisputeConfig) -> Result<(), Error> {
+            if !config.is_valid() {
+                return Err(Error::InvalidConfig);
+            }
+            self.dispute_config.set(&Some(config));
+            Ok(())
+        }
+
+        fn ensure_no_round(&self) -> Result<(), Error> {
+            if self.round.get_or_default().is_some_and(|round| self.env().block_number() < round.reveal_ends) {
+                return Err(Error::RoundInProgress);
+            }
+            Ok(())
+        }
+
+        fn ensure_not_paused(&self, operation: Operation) -> Result<(), Error> {
+            if self.is_paused(operation) {
+                return Err(Error::OperationPaused(operation));
+            }
+            Ok(())
+        }
+
+        fn ensure_role(&self, role: Role) -> Result<(), Error> {
+            if !self.has_role(role, self.env().caller()) {
+                return Err(Error::MissingRole(role));
+            }
+            Ok(())
+        }
+
+        /// Removes `delegator`'s delegation and returns how many of the
+        /// delegated votes were taken back from the delegate.
+        fn drop_delegation(&mut self, delegator: AccountId, delegation: &Delegation) -> u128 {
+            let delegate = delegation.delegate;
+            let received = self.delegated_in.get(delegate).unwrap_or(0);
+            let refunded = delegation.remaining.min(received);
+            self.delegated_in.insert(delegate, &(received - refunded));
+            self.unfund(delegator, delegate);
+            self.delegations.remove(delegator);
+
+            let hops = self.delegation_depth.get(delegator).unwrap_or(0) + 1;
+            self.shift_delegation_hops(delegate, hops, 0);
+
+            refunded
+        }
+
+        /// Moves one of the delegations to `account` from completing a chain
+        /// of `from` hops to one of `to` hops, where zero stands for no
+        /// delegation, and carries any change of `account`'s depth down its
+        /// own delegation chain.
+        fn shift_delegation_hops(&mut self, mut account: AccountId, mut from: u32, mut to: u32) {
+            loop {
+                if from > 0 {
+                    let count = self.delegation_hops.get((account, from)).unwrap_or(1) - 1;
+                    if count == 0 {
+                        self.delegation_hops.remove((account, from));
+                    } else {
+                        self.delegation_hops.insert((account, from), &count);
+                    }
+                }
+                if to > 0 {
+                    let count = self.delegation_hops.get((account, to)).unwrap_or(0);
+                    self.delegation_hops.insert((account, to), &(count + 1));
+                }
+
+                let depth = self.delegation_depth.get(account).unwrap_or(0);
+                let new_depth = if to > depth {
+                    to
+                } else {
+                    (1..=depth).rev().find(|&hops| self.delegation_hops.contains((account, hops))).unwrap_or(0)
+                };
+                if new_depth == depth {
+                    return
+                }
+                if new_depth == 0 {
+                    self.delegation_depth.remove(account);
+                } else {
+                    self.delegation_depth.insert(account, &new_depth);
+                }
+
+                let Some(delegation) = self.delegations.get(account) else {
+                    return
+                };
+                (account, from, to) = (delegation.delegate, depth + 1, new_depth + 1);
+            }
+        }
+
+        /// Lists `delegator` among the delegations funding `delegate`'s
+        /// delegated votes, unless it already is.
+        fn fund(&mut self, delegator: AccountId, delegate: AccountId) {
+            if self.funding_index.contains(delegator) {
+                return
+            }
+            let index = self.funding_count.get(delegate).unwrap_or(0);
+            self.funding.insert((delegate, index), &delegator);
+            self.funding_index.insert(delegator, &index);
+            self.funding_count.insert(delegate, &(index + 1));
+        }
+
+        /// Takes `delegator` off the delegations funding `delegate`'s
+        /// delegated votes.
+        fn unfund(&mut self, delegator: AccountId, delegate: AccountId) {
+            let Some(index) = self.funding_index.take(delegator) else {
+                return
+            };
+            // Swap-remove, like the voter registry.
+            let last_index = self.funding_count.get(delegate).unwrap_or(1) - 1;
+            if index != last_index {
+                if let Some(last) = self.funding.get((delegate, last_index)) {
+                    self.funding.insert((delegate, index), &last);
+                    self.funding_index.insert(last, &index);
+                }
+            }
+            self.funding.remove((delegate, last_index));
+            if last_index == 0 {
+                self.funding_count.remove(delegate);
+            } else {
+                self.funding_count.insert(delegate, &last_index);
+            }
+        }
+
+        /// Splits `amount` of `delegate`'s delegated votes between the
+        /// delegations funding them, most recently listed first.
+        fn delegated_spend(&self, delegate: AccountId, amount: u128) -> Vec<(AccountId, u128)> {
+            let mut spend = Vec::new();
+            let mut left = amount;
+            let mut index = self.funding_count.get(delegate).unwrap_or(0);
+            while left > 0 && index > 0 {
+                index -= 1;
+                let Some(delegator) = self.funding.get((delegate, index)) else {
+                    continue
+                };
+                let remaining = self.delegations.get(delegator).map_or(0, |delegation| delegation.remaining);
+                let votes = left.min(remaining);
+                if votes > 0 {
+                    spend.push((delegator, votes));
+                    left -= votes;
+                }
+            }
+            spend
+        }
+
+        /// Takes the votes `delegated_spend` split up from the delegations
+        /// and from `delegate`'s delegated votes.
+        fn spend_delegated(&mut self, delegate: AccountId, spend: &[(AccountId, u128)]) {
+            let mut total: u128 = 0;
+            for &(delegator, votes) in spend {
+                let Some(mut delegation) = self.delegations.get(delegator) else {
+                    continue
+                };
+                delegation.remaining = delegation.remaining.saturating_sub(votes);
+                if delegation.remaining == 0 {
+                    self.unfund(delegator, delegate);
+                }
+                self.delegations.insert(delegator, &delegation);
+                total = total.saturating_add(votes);
+            }
+            if total > 0 {
+                let received = self.delegated_in.get(delegate).unwrap_or(0);
+                self.delegated_in.insert(delegate, &received.saturating_sub(total));
+            }
+        }
+
+        /// Gives back `votes` that `delegate` spent from `delegator`'s
+        /// delegation: to the delegation while it is still in place, and to
+        /// the delegator otherwise.
+        fn refund_delegated(&mut self, delegator: AccountId, delegate: AccountId, votes: u128) {
+            if votes == 0 {
+                return
+            }
+            match self.delegations.get(delegator) {
+                Some(mut delegation) if delegation.delegate == delegate => {
+                    delegation.remaining = delegation.remaining.saturating_add(votes);
+                    self.delegations.insert(delegator, &delegation);
+                    self.fund(delegator, delegate);
+                    let received = self.delegated_in.get(delegate).unwrap_or(0);
+                    self.delegated_in.insert(delegate, &received.saturating_add(votes));
+                }
+                _ => {
+                    if let Ok(mut voter) = self.load_voter(delegator) {
+                        voter.available_votes = voter.available_votes.saturating_add(votes);
+                        self.store_voter(&voter);
+                    }
+                }
+            }
+        }
+
+        /// Loads a voter with any pending epoch refill and reputation decay
+        /// applied. Refills are held back while the voter has votes locked
+        /// in a commitment.
+        fn load_voter(&self, account: AccountId) -> Result<Voter, Error> {
+            let mut voter = self.stored_voter(account).ok_or(Error::UnregisteredVoter)?;
+            voter.reputation = self.decayed(voter.reputation, self.reputation_clocks.get(account));
+            if let Some(config) = self.epoch_config.get_or_default().filter(|_| !self.votes_locked(account)) {
+                config.refill(&mut voter, self.refilled_at.get(account), self.env().block_number())?;
+            }
+            Ok(voter)
+        }
+
+        /// Whether `account` has an unrevealed commitment in a round that
+        /// has not ended.
+        fn votes_locked(&self, account: AccountId) -> bool {
+            self.round
+                .get_or_default()
+                .filter(|round| self.env().block_number() < round.reveal_ends)
+                .is_some_and(|round| self.commitments.contains((round.id, account)))
+        }
+
+        /// Half-lives elapsed under every decay configuration so far, in
+        /// units of `2^-DECAY_FRACTION_BITS`. Stands still while decay is
+        /// disabled.
+        fn decay_clock(&self) -> u128 {
+            let halvings = self.decay_halvings.get_or_default();
+            match self.decay_config.get_or_default() {
+                Some(config) => halvings.saturating_add(config.halvings(self.env().block_timestamp())),
+                None => halvings,
+            }
+        }
+
+        /// Decays `reputation` written when the decay clock read
+        /// `written_at`, or before decay was first enabled if `None`.
+        fn decayed(&self, reputation: Reputation, written_at: Option<u128>) -> Reputation {
+            decay(reputation, self.decay_clock().saturating_sub(written_at.unwrap_or(0)))
+        }
+
+        /// Writes `account`'s reputation in `category`, which decays from
+        /// now on.
+        fn set_reputation_in(&mut self, account: AccountId, category: CategoryId, reputation: Reputation) {
+            let score = CategoryScore { reputation, decay_clock: self.decay_clock() };
+            self.category_reputation.insert((account, category), &score);
+        }
+
+        /// Takes `amount` out of `account`'s positive category scores, in
+        /// proportion to its aggregate `reputation` before the slash, and
+        /// returns what each category lost.
+        fn slash_categories(
+            &mut self,
+            account: AccountId,
+            amount: Reputation,
+            reputation: Reputation,
+        ) -> Result<Vec<(CategoryId, Reputation)>, Error> {
+            let mut taken = Vec::new();
+            if amount <= 0 || reputation <= 0 {
+                return Ok(taken)
+            }
+            let amount = amount.min(reputation).unsigned_abs();
+            for category in 0..self.category_count.get_or_default() {
+                let score = self.reputation_in(account, category);
+                if score <= 0 {
+                    continue
+                }
+                let loss = proportion(score, amount, reputation.unsigned_abs())?;
+                if loss != 0 {
+                    self.set_reputation_in(account, category, score - loss);
+                    taken.push((category, loss));
+                }
+            }
+            Ok(taken)
+        }
+
+        /// Writes a voter loaded through `load_voter` back to storage.
+        fn store_voter(&mut self, voter: &Voter) {
+            self.convert_voter(voter.address);
+            let previous = self
+                .voters_v2
+                .get(voter.address)
+                .map(|stored| self.decayed(stored.reputation, self.reputation_clocks.get(voter.address)));
+            self.voters_v2.insert(voter.address, voter);
+            self.rerank(voter.address, rank_key(voter.reputation, self.decay_clock()));
+            if previous != Some(voter.reputation) {
+                self.record_reputation(voter.address, previous.unwrap_or(0), voter.reputation);
+            }
+            self.reputation_clocks.insert(voter.address, &self.decay_clock());
+            if let Some(config) = self.epoch_config.get_or_default().filter(|_| !self.votes_locked(voter.address)) {
+                let epoch_start = config.epoch_start(self.env().block_number());
+                self.refilled_at.insert(voter.address, &epoch_start);
+            }
+        }
+
+        /// Moves a version 1 voter to the current layout, giving it a
+        /// registry position, a place in the ranking and a share of the
+        /// total reputation. Does nothing for any other account.
+        fn convert_voter(&mut self, account: AccountId) {
+            if self.storage_version() >= STORAGE_VERSION {
+                return
+            }
+            let Some(voter) = self.voters.take(account) else {
+                return
+            };
+            let voter = Voter::from(voter);
+            if !self.voter_indices.contains(account) {
+                self.index_voter(account);
+            }
+            // The version 1 reputation stood since before any checkpoint.
+            self.checkpoints.insert((Some(account), 0), &Checkpoint {
+                block: 0,
+                reputation: voter.reputation,
+                decay_clock: 0,
+            });
+            self.checkpoint_counts.insert(Some(account), &1);
+            self.voters_v2.insert(account, &voter);
+            // Version 1 reputation decays from before decay was enabled.
+            self.rerank(account, rank_key(voter.reputation, 0));
+            self.record_reputation(account, 0, self.decayed(voter.reputation, None));
+        }
+
+        /// Appends `account` to the registry.
+        fn index_voter(&mut self, account: AccountId) {
+            let index = self.voter_count.get_or_default();
+            self.voters_by_index.insert(index, &account);
+            self.voter_indices.insert(account, &index);
+            self.voter_count.set(&(index + 1));
+        }
+
+        /// `account`'s reputation at the end of `block`, decayed up to
+        /// `clock`.
+        fn decayed_reputation_at(&self, account: AccountId, block: BlockNumber, clock: u128) -> Result<Reputation, Error> {
+            let checkpoint = self.checkpoint_at(Some(account), block)?;
+            // A version 1 voter has no history until converted, and held
+            // its version 1 reputation, decaying from clock zero, all along.
+            match self.voters.get(account) {
+                Some(voter) if self.storage_version() < STORAGE_VERSION => Ok(decay(voter.reputation, clock)),
+                _ => Ok(checkpoint.map_or(0, |checkpoint| {
+                    decay(checkpoint.reputation, clock.saturating_sub(checkpoint.decay_clock))
+                })),
+            }
+        }
+
+        /// Checkpoints a change of `account`'s reputation from `previous`,
+        /// both decayed up to now, and the resulting change of the total.
+        fn record_reputation(&mut self, account: AccountId, previous: Reputation, reputation: Reputation) {
+            let total = self.total_reputation().saturating_sub(previous).saturating_add(reputation);
+            let clock = self.decay_clock();
+            self.total_reputation.set(&total);
+            self.total_decay_clock.set(&clock);
+            self.push_checkpoint(Some(account), reputation, clock);
+            self.push_checkpoint(None, total, clock);
+        }
+
+        /// Appends to a reputation history. Later changes within the same
+        /// block overwrite that block's checkpoint.
+        fn push_checkpoint(&mut self, account: HistoryKey, reputation: Reputation, decay_clock: u128) {
+            let block = self.env().block_number();
+            let count = self.checkpoint_counts.get(account).unwrap_or(0);
+            let last = count.checked_sub(1);
+            let index = match last.and_then(|last| self.checkpoints.get((account, last))) {
+                Some(checkpoint) if checkpoint.block == block => count - 1,
+                _ => {
+                    self.checkpoint_counts.insert(account, &(count + 1));
+                    count
+                }
+            };
+            self.checkpoints.insert((account, index), &Checkpoint { block, reputation, decay_clock });
+        }
+
+        /// Binary searches a reputation history for its checkpoint standing
+        /// at the end of `block`.
+        fn checkpoint_at(&self, account: HistoryKey, block: BlockNumber) -> Result<Option<Checkpoint>, Error> {
+            if block >= self.env().block_number() {
+                return Err(Error::BlockNotPast);
+            }
+
+            let count = written_by(self.checkpoint_counts.get(account).unwrap_or(0), block, |index| {
+                self.checkpoints.get((account, index)).map(|checkpoint| checkpoint.block)
+            });
+            Ok(count.checked_sub(1).and_then(|index| self.checkpoints.get((account, index))))
+        }
+
+        fn is_voter(&self, account: AccountId) -> bool {
+            self.voters_v2.contains(account) || self.voters.contains(account)
+        }
+
+        /// Reads a voter as last written, converting an older layout.
+        fn stored_voter(&self, account: AccountId) -> Option<Voter> {
+            self.voters_v2.get(account).or_else(|| self.voters.get(account).map(Voter::from))
+        }
+
+        /// Moves `account` to its place in the ranking for `key`.
+        fn rerank(&mut self, account: AccountId, key: RankKey) {
+            if self.ranking.get(account).is_some_and(|node| node.key == key) {
+                return
+            }
+            self.unrank(account);
+
+            let height = self.rank_height(account);
+            let found = self.rank_search(|next, node| ranks_before(node.key, next, key, account));
+            let (prev, position) = found[0];
+            let levels = height.max(self.ranking_height.get_or_default());
+            for level in 0..levels {
+                let (from, from_position) = found[usize::from(level)];
+                let mut link = self.rank_link(from, level);
+                if level < height {
+                    // Split the link around the new voter.
+                    let skipped = position - from_position;
+                    self.rank_links.insert((Some(account), level), &RankLink { next: link.next, span: link.span - skipped });
+                    link = RankLink { next: Some(account), span: skipped + 1 };
+                } else {
+                    link.span += 1;
+                }
+                self.rank_links.insert((from, level), &link);
+            }
+            self.ranking_height.set(&levels);
+
+            match self.rank_link(Some(account), 0).next {
+                Some(next) => self.set_rank_prev(next, Some(account)),
+                None => self.ranking_tail.set(&Some(account)),
+            }
+            self.ranking.insert(account, &RankNode { key, height, prev });
+            self.ranked_count.set(&(self.ranked_count.get_or_default() + 1));
+        }
+
+        /// Takes `account` out of the ranking, if it is in it.
+        fn unrank(&mut self, account: AccountId) {
+            let Some(node) = self.ranking.take(account) else {
+                return
+            };
+            let found = self.rank_search(|next, next_node| ranks_before(next_node.key, next, node.key, account));
+            let height = self.ranking_height.get_or_default();
+            for level in 0..height {
+                let (from, _) = found[usize::from(level)];
+                let mut link = self.rank_link(from, level);
+                if level < node.height {
+                    let own = self.rank_links.take((Some(account), level)).unwrap_or(RankLink { next: None, span: 1 });
+                    link = RankLink { next: own.next, span: link.span + own.span - 1 };
+                } else {
+                    link.span -= 1;
+                }
+                self.rank_links.insert((from, level), &link);
+            }
+
+            match self.rank_link(node.prev, 0).next {
+                Some(next) => self.set_rank_prev(next, node.prev),
+                None => self.ranking_tail.set(&node.prev),
+            }
+            self.ranked_count.set(&(self.ranked_count.get_or_default() - 1));
+
+            // Drop head levels nobody is linked on any more.
+            let mut height = height;
+            while height > 0 && self.rank_link(None, height - 1).next.is_none() {
+                height -= 1;
+                self.rank_links.remove((RankFrom::None, height));
+            }
+            self.ranking_height.set(&height);
+        }
+
+        fn set_rank_prev(&mut self, account: AccountId, prev: Option<AccountId>) {
+            if let Some(mut node) = self.ranking.get(account) {
+                node.prev = prev;
+                self.ranking.insert(account, &node);
+            }
+        }
+
+        /// Link on `level` from `from`, or from the head for `None`.
+        fn rank_link(&self, from: RankFrom, level: u8) -> RankLink {
+            self.rank_links.get((from, level)).unwrap_or(RankLink {
+                next: None,
+                span: if from.is_none() { self.ranked_count.get_or_default() } else { 0 },
+            })
+        }
+
+        /// Skip list levels `account` is linked on, one plus the number of
+        /// quarter chances it wins, drawn from a hash of the account so every
+        /// node computes the same.
+        fn rank_height(&self, account: AccountId) -> u8 {
+            let hash = self.env().hash_encoded::<ink::env::hash::Blake2x256, _>(&account);
+            let draw = u32::from_le_bytes([hash[0], hash[1], hash[2], hash[3]]);
+            (1 + draw.trailing_zeros() / 2).min(RANK_LEVELS as u32) as u8
+        }
+
+        /// Walks the ranking down from the head and returns, on every level,
+        /// the last voter `before` accepts, or the head for `None`, with its
+        /// 1-based position, zero for the head. `before` must accept a
+        /// prefix of the ranking.
+        fn rank_search(
+            &self,
+            before: impl Fn(AccountId, &RankNode) -> bool,
+        ) -> [(RankFrom, u32); RANK_LEVELS] {
+            let mut found = [(None, 0); RANK_LEVELS];
+            let (mut cursor, mut position) = (None, 0);
+            for level in (0..self.ranking_height.get_or_default()).rev() {
+                loop {
+                    let link = self.rank_link(cursor, level);
+                    match link.next {
+                        Some(next) if self.ranking.get(next).is_some_and(|node| before(next, &node)) => {
+                            cursor = Some(next);
+                            position += link.span;
+                        }
+                        _ => break,
+                    }
+                }
+                found[usize::from(level)] = (cursor, position);
+            }
+            found
+        }
+
+        /// Voter at 1-based `position` in the ranking.
+        fn ranked_at(&self, position: u32) -> Option<AccountId> {
+            let (mut cursor, mut reached) = (None, 0u32);
+            for level in (0..self.ranking_height.get_or_default()).rev() {
+                loop {
+                    let link = self.rank_link(cursor, level);
+                    match link.next {
+                        Some(next) if reached + link.span <= position => {
+                            cursor = Some(next);
+                            reached += link.span;
+                        }
+                        _ => break,
+                    }
+                }
+                if reached == position {
+                    return cursor
+                }
+            }
+            None
+        }
+
+        /// Picks up to `n` jurors outside `parties` at ranking positions
+        /// drawn independently from `seed`. Once a bounded number of draws
+        /// is spent, the rest are taken walking on from the last position
+        /// drawn, wrapping around.
+        fn pick_jurors(&self, seed: [u8; 32], parties: &[AccountId], n: u32) -> Vec<AccountId> {
+            let ranked = self.ranked_count.get_or_default();
+            let mut jurors: Vec<AccountId> = Vec::new();
+            if ranked == 0 {
+                return jurors
+            }
+            let eligible = |jurors: &Vec<AccountId>, account: &AccountId| {
+                !parties.contains(account) && !jurors.contains(account)
+            };
+            let mut position = 1;
+            for attempt in 0..n.saturating_mul(4) {
+                if jurors.len() as u32 >= n {
+                    break
+                }
+                let hash = self.env().hash_encoded::<ink::env::hash::Blake2x256, _>(&(seed, attempt));
+                position = u32::from_le_bytes([hash[0], hash[1], hash[2], hash[3]]) % ranked + 1;
+                if let Some(account) = self.ranked_at(position).filter(|account| eligible(&jurors, account)) {
+                    jurors.push(account);
+                }
+            }
+            let mut cursor = self.ranked_at(position);
+            for _ in 0..ranked {
+                let Some(current) = cursor else { break };
+                if jurors.len() as u32 >= n {
+                    break
+                }
+                if eligible(&jurors, &current) {
+                    jurors.push(current);
+                }
+                cursor = self.rank_link(Some(current), 0).next.or(self.rank_link(None, 0).next);
+            }
+            jurors
+        }
+
+        /// Extends the entropy hash chain with the caller and the current
+        /// block. Later extensions within the same block overwrite that
+        /// block's link.
+        fn mix_entropy(&mut self) {
+            let block = self.env().block_number();
+            let count = self.entropy_count.get_or_default();
+            let last = count.checked_sub(1).and_then(|last| self.entropy.get(last));
+            let previous = last.map_or([0; 32], |(_, value)| value);
+            let link = (previous, self.env().caller(), block, self.env().block_timestamp());
+            let value = self.env().hash_encoded::<ink::env::hash::Blake2x256, _>(&link);
+            let index = match last {
+                Some((written, _)) if written == block => count - 1,
+                _ => {
+                    self.entropy_count.set(&(count + 1));
+                    count
+                }
+            };
+            self.entropy.insert(index, &(block, value));
+        }
+
+        /// The entropy hash chain as it stood at the end of `block`.
+        fn entropy_at(&self, block: BlockNumber) -> [u8; 32] {
+            let count = written_by(self.entropy_count.get_or_default(), block, |index| {
+                self.entropy.get(index).map(|(written, _)| written)
+            });
+            count.checked_sub(1).and_then(|index| self.entropy.get(index)).map_or([0; 32], |(_, value)| value)
+        }
+
+        /// Collects up to `n` voters from `start`, following `step`.
+        fn walk_ranking(
+            &self,
+            start: Option<AccountId>,
+            n: u32,
+            step: impl Fn(&Self, AccountId) -> Option<AccountId>,
+        ) -> Vec<Voter> {
+            let mut voters: Vec<Voter> = Vec::new();
+            let mut cursor = start;
+            while let Some(current) = cursor {
+                if voters.len() as u32 >= n.min(MAX_PAGE_SIZE) {
+                    break
+                }
+                voters.extend(self.load_voter(current).ok());
+                cursor = step(self, current);
+            }
+            voters
+        }
+
+        /// Loads `limit` voters from registry position `offset` onwards.
+        fn voters_in(&self, offset: u32, limit: u32) -> Result<Vec<Voter>, Error> {
+            let end = offset.saturating_add(limit).min(self.voter_count.get_or_default());
+            let mut voters: Vec<Voter> = Vec::new();
+            for index in offset..end {
+                let address = self.voters_by_index.get(index).ok_or(Error::UnregisteredVoter)?;
+                voters.push(self.load_voter(address)?);
+            }
+            Ok(voters)
+        }
+    }
+
+    /// Unit tests run against the ink! off-chain environment.
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        type Event = <OptionsAndFutures as ::ink::reflect::ContractEventBase>::Type;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(caller);
+        }
+
+        fn recorded_events() -> Vec<Event> {
+            ink::env::test::recorded_events()
+                .map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).expect("invalid event data"))
+                .collect()
+        }
+
+        /// Deploys the contract from Alice and registers Bob and Charlie.
+        fn setup() -> OptionsAndFutures {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut contract = OptionsAndFutures::new();
+            contract.add_voter(accounts.bob, 10).unwrap();
+            contract.add_voter(accounts.charlie, 10).unwrap();
+            contract
+        }
+
+        #[ink::test]
+        fn constructor_emits_ownership_transferred() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let _contract = OptionsAndFutures::new();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 1);
+            match &events[0] {
+                Event::OwnershipTransferred(event) => {
+                    assert_eq!(event.previous_owner, None);
+                    assert_eq!(event.new_owner, Some(accounts.alice));
+                }
+                _ => panic!("expected OwnershipTransferred"),
+            }
+        }
+
+        #[ink::test]
+        fn add_voter_emits_voter_added() {
+            let accounts = accounts();
+            let _contract = setup();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 3);
+            match &events[1] {
+                Event::VoterAdded(event) => {
+                    assert_eq!(event.voter, accounts.bob);
+                    assert_eq!(event.available_votes, 10);
+                }
+                _ => panic!("expected VoterAdded"),
+            }
+        }
+
+        #[ink::test]
+        fn vote_emits_vote_cast() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, -4, None).unwrap();
+
+            let events = recorded_events();
+            match events.last() {
+                Some(Event::VoteCast(event)) => {
+                    assert_eq!(event.voter, accounts.bob);
+                    assert_eq!(event.candidate, accounts.charlie);
+                    assert_eq!(event.votes, -4);
+                    assert_eq!(event.reputation, -4);
+                    assert_eq!(event.vote_id, 0);
+                }
+                _ => panic!("expected VoteCast"),
+            }
+        }
+
+        #[ink::test]
+        fn remove_voter_emits_voter_removed() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            contract.remove_voter(accounts.bob).unwrap();
+
+            match recorded_events().last() {
+                Some(Event::VoterRemoved(event)) => assert_eq!(event.voter, accounts.bob),
+                _ => panic!("expected VoterRemoved"),
+            }
+        }
+
+        #[ink::test]
+        fn failed_calls_emit_nothing() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.add_voter(accounts.django, 1), Err(Error::MissingRole(Role::Registrar)));
+            assert_eq!(contract.vote(accounts.charlie, 11, None), Err(Error::InsufficientVotes));
+            assert_eq!(contract.vote(accounts.bob, 1, None), Err(Error::VoterEqualToCandidate));
+
+            assert_eq!(recorded_events().len(), 3);
+        }
+
+        #[ink::test]
+        fn remove_voter_keeps_registry_consistent() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 5).unwrap();
+
+            contract.remove_voter(accounts.bob).unwrap();
+
+            assert_eq!(contract.voter_count(), 2);
+            assert_eq!(contract.get_voter(accounts.bob), None);
+            let addresses: Vec<AccountId> = contract
+                .get_voters()
+                .unwrap()
+                .into_iter()
+                .map(|voter| voter.address)
+                .collect();
+            assert_eq!(addresses, vec![accounts.django, accounts.charlie]);
+        }
+
+        #[ink::test]
+        fn re_adding_voter_is_rejected_until_removed() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            assert_eq!(contract.add_voter(accounts.bob, 3), Err(Error::VoterAlreadyRegistered));
+            assert_eq!(contract.voter_count(), 2);
+
+            contract.remove_voter(accounts.bob).unwrap();
+            contract.add_voter(accounts.bob, 3).unwrap();
+            assert_eq!(contract.voter_count(), 2);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 3);
+        }
+
+        #[ink::test]
+        fn get_voters_page_slices_registry() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 5).unwrap();
+            contract.add_voter(accounts.eve, 5).unwrap();
+
+            let page: Vec<AccountId> = contract
+                .get_voters_page(1, 2)
+                .unwrap()
+                .into_iter()
+                .map(|voter| voter.address)
+                .collect();
+            assert_eq!(page, vec![accounts.charlie, accounts.django]);
+            assert_eq!(contract.get_voters_page(3, 10).unwrap().len(), 1);
+            assert!(contract.get_voters_page(10, 10).unwrap().is_empty());
+            assert!(contract.get_voters_page(u32::MAX, u32::MAX).unwrap().is_empty());
+        }
+
+        #[ink::test]
+        fn vote_ledger_tracks_pairs() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, None).unwrap();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            contract.vote(accounts.charlie, -1, None).unwrap();
+            contract.vote(accounts.django, 2, None).unwrap();
+            set_caller(accounts.django);
+            contract.vote(accounts.charlie, 4, None).unwrap();
+
+            assert_eq!(
+                contract.vote_between(accounts.bob, accounts.charlie),
+                Some(VoteRecord {
+                    net: 2,
+                    total_cast: 4,
+                    effect: 2,
+                    decay_clock: 0,
+                    epoch: 0,
+                    refundable: 4,
+                    removals: (0, 0),
+                    delegated: Vec::new(),
+                    categories: Vec::new(),
+                    last_block: 1,
+                })
+            );
+            assert_eq!(contract.vote_between(accounts.charlie, accounts.bob), None);
+
+            let given: Vec<AccountId> = contract
+                .votes_given(accounts.bob, 0, 10)
+                .into_iter()
+                .map(|(candidate, _)| candidate)
+                .collect();
+            assert_eq!(given, vec![accounts.charlie, accounts.django]);
+            let page: Vec<AccountId> = contract
+                .votes_given(accounts.bob, 1, 10)
+                .into_iter()
+                .map(|(candidate, _)| candidate)
+                .collect();
+            assert_eq!(page, vec![accounts.django]);
+
+            let received: Vec<(AccountId, i128)> = contract
+                .votes_received(accounts.charlie, 0, 10)
+                .into_iter()
+                .map(|(voter, record)| (voter, record.net))
+                .collect();
+            assert_eq!(received, vec![(accounts.bob, 2), (accounts.django, 4)]);
+            assert_eq!(contract.votes_received(accounts.charlie, 0, 1).len(), 1);
+            assert!(contract.votes_received(accounts.charlie, 2, 10).is_empty());
+        }
+
+        #[ink::test]
+        fn removal_lapses_ledger_entries() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, None).unwrap();
+            contract.vote(accounts.django, 2, None).unwrap();
+
+            // A re-added voter cannot retract votes cast before its removal
+            // for a refund.
+            set_caller(accounts.alice);
+            contract.remove_voter(accounts.bob).unwrap();
+            contract.add_voter(accounts.bob, 4).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(contract.retract_vote(accounts.charlie, 3), Err(Error::RetractExceedsVotes));
+            assert_eq!(contract.vote_between(accounts.bob, accounts.charlie), None);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 3);
+
+            // Nor do old votes move a re-added candidate's fresh reputation.
+            set_caller(accounts.django);
+            contract.vote(accounts.charlie, 1, None).unwrap();
+            set_caller(accounts.alice);
+            contract.remove_voter(accounts.charlie).unwrap();
+            contract.add_voter(accounts.charlie, 10).unwrap();
+            set_caller(accounts.django);
+            assert_eq!(contract.retract_vote(accounts.charlie, 1), Err(Error::RetractExceedsVotes));
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 2, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 2);
+            let given: Vec<_> = contract.votes_given(accounts.bob, 0, 10).into_iter().map(|(candidate, _)| candidate).collect();
+            assert_eq!(given, vec![accounts.charlie]);
+        }
+
+        #[ink::test]
+        fn retract_vote_refunds_and_restores_reputation() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, -6, None).unwrap();
+            contract.retract_vote(accounts.charlie, 4).unwrap();
+
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, -2);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 8);
+            assert_eq!(
+                contract.vote_between(accounts.bob, accounts.charlie),
+                Some(VoteRecord {
+                    net: -2,
+                    total_cast: 2,
+                    effect: -2,
+                    decay_clock: 0,
+                    epoch: 0,
+                    refundable: 2,
+                    removals: (0, 0),
+                    delegated: Vec::new(),
+                    categories: Vec::new(),
+                    last_block: 0,
+                })
+            );
+            match recorded_events().last() {
+                Some(Event::VoteRetracted(event)) => {
+                    assert_eq!(event.votes, -4);
+                    assert_eq!(event.reputation, -2);
+                }
+                _ => panic!("expected VoteRetracted"),
+            }
+
+            assert_eq!(contract.retract_vote(accounts.charlie, 3), Err(Error::RetractExceedsVotes));
+            assert_eq!(contract.retract_vote(accounts.alice, 1), Err(Error::UnregisteredVoter));
+        }
+
+        #[ink::test]
+        fn retract_without_prior_vote_fails() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.retract_vote(accounts.charlie, 1), Err(Error::RetractExceedsVotes));
+        }
+
+        #[ink::test]
+        fn reallocate_moves_votes_between_candidates() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 5, None).unwrap();
+            contract.reallocate(accounts.charlie, accounts.django, 3, None).unwrap();
+
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 2);
+            assert_eq!(contract.get_voter(accounts.django).unwrap().reputation, 3);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 5);
+
+            assert_eq!(
+                contract.reallocate(accounts.charlie, accounts.django, 3, None),
+                Err(Error::RetractExceedsVotes)
+            );
+            assert_eq!(
+                contract.reallocate(accounts.charlie, accounts.bob, 1, None),
+                Err(Error::VoterEqualToCandidate)
+            );
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 2);
+        }
+
+        #[ink::test]
+        fn quadratic_cost_grows_with_votes_on_same_candidate() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut contract = OptionsAndFutures::with_cost_model(CostModel::Quadratic);
+            contract.add_voter(accounts.bob, 20).unwrap();
+            contract.add_voter(accounts.charlie, 20).unwrap();
+            contract.add_voter(accounts.django, 20).unwrap();
+
+            assert_eq!(contract.vote_cost(accounts.bob, accounts.charlie, 3), Ok(9));
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 11);
+
+            // Downvotes count towards the same total: 4² - 3² = 7.
+            assert_eq!(contract.vote_cost(accounts.bob, accounts.charlie, -1), Ok(7));
+            // A fresh candidate starts from zero again.
+            assert_eq!(contract.vote_cost(accounts.bob, accounts.django, 3), Ok(9));
+            assert_eq!(contract.vote(accounts.charlie, 2, None), Err(Error::InsufficientVotes));
+            // A cost past `u128::MAX` is an error rather than a cheap vote.
+            assert_eq!(contract.vote_cost(accounts.bob, accounts.charlie, i128::MAX), Err(Error::VotesOverflow));
+
+            contract.retract_vote(accounts.charlie, 1).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 16);
+            assert_eq!(contract.vote_cost(accounts.bob, accounts.charlie, 1), Ok(5));
+        }
+
+        #[ink::test]
+        fn linear_cost_is_default() {
+            let accounts = accounts();
+            let contract = setup();
+
+            assert_eq!(contract.cost_model(), CostModel::Linear);
+            assert_eq!(contract.vote_cost(accounts.bob, accounts.charlie, -7), Ok(7));
+        }
+
+        fn advance_blocks(blocks: u32) {
+            for _ in 0..blocks {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+        }
+
+        #[ink::test]
+        fn epochs_reset_available_votes() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_epoch_config(10, 6, RefillMode::Reset).unwrap();
+            assert_eq!(contract.current_epoch(), Some((0, 10)));
+
+            // Starting epochs refills everyone straight away.
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 5, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 1);
+
+            advance_blocks(9);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 1);
+            advance_blocks(1);
+            assert_eq!(contract.current_epoch(), Some((1, 20)));
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+            contract.vote(accounts.charlie, 6, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 0);
+        }
+
+        #[ink::test]
+        fn retract_only_refunds_votes_from_the_current_epoch() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_epoch_config(10, 6, RefillMode::Reset).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 6, None).unwrap();
+            advance_blocks(10);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+
+            // The refill already restored what last epoch's votes cost, so
+            // retracting them frees no credit.
+            contract.vote(accounts.charlie, 2, None).unwrap();
+            contract.retract_vote(accounts.charlie, 5).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 3);
+            contract.retract_vote(accounts.charlie, 3).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 0);
+        }
+
+        #[ink::test]
+        fn epochs_top_up_for_every_missed_epoch() {
+            let accounts = accounts();
+            let mut contract = setup();
+            advance_blocks(3);
+            contract.set_epoch_config(5, 2, RefillMode::TopUp).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 12);
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 12, None).unwrap();
+            advance_blocks(15);
+            assert_eq!(contract.current_epoch(), Some((3, 23)));
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+        }
+
+        #[ink::test]
+        fn epoch_config_is_admin_only_and_can_be_disabled() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_epoch_config(10, 1, RefillMode::Reset),
+                Err(Error::MissingRole(Role::Admin))
+            );
+
+            set_caller(accounts.alice);
+            contract.set_epoch_config(10, 1, RefillMode::Reset).unwrap();
+            contract.set_epoch_config(0, 1, RefillMode::Reset).unwrap();
+            assert_eq!(contract.epoch_config(), None);
+            assert_eq!(contract.current_epoch(), None);
+        }
+
+        fn set_block_timestamp(timestamp: Timestamp) {
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(timestamp);
+        }
+
+        #[ink::test]
+        fn reputation_decays_by_half_life() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            contract.set_reputation_half_life(1_000).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 8, None).unwrap();
+            contract.vote(accounts.django, -2, None).unwrap();
+            set_caller(accounts.charlie);
+            contract.vote(accounts.django, -6, None).unwrap();
+
+            set_block_timestamp(1_000);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 4);
+            assert_eq!(contract.get_voter(accounts.django).unwrap().reputation, -4);
+
+            // 8 * 2^(-1.5) = 2.83, rounded towards zero.
+            set_block_timestamp(1_500);
+            let reputations: Vec<Reputation> = contract
+                .get_voters()
+                .unwrap()
+                .into_iter()
+                .map(|voter| voter.reputation)
+                .collect();
+            assert_eq!(reputations, vec![0, 2, -2]);
+
+            // Writes store the decayed value and restart the clock.
+            set_caller(accounts.django);
+            contract.vote(accounts.charlie, 2, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 4);
+            set_block_timestamp(2_500);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 2);
+
+            set_block_timestamp(u64::MAX);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 0);
+        }
+
+        #[ink::test]
+        fn decay_only_applies_after_it_is_enabled() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 8, None).unwrap();
+
+            set_block_timestamp(5_000);
+            set_caller(accounts.alice);
+            contract.set_reputation_half_life(1_000).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 8);
+
+            set_block_timestamp(7_000);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 2);
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_reputation_half_life(0), Err(Error::MissingRole(Role::Admin)));
+        }
+
+        #[ink::test]
+        fn decay_applies_to_retractions_and_outlives_disabling() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_reputation_half_life(1_000).unwrap();
+
+            // Retracting takes back what the votes are worth after decay.
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 8, None).unwrap();
+            set_block_timestamp(2_000);
+            assert_eq!(contract.vote_between(accounts.bob, accounts.charlie).unwrap().effect, 2);
+            contract.retract_vote(accounts.charlie, 8).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 0);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 10);
+
+            // Disabling decay keeps what has decayed so far, and re-enabling
+            // it carries on from there.
+            contract.vote(accounts.charlie, 4, None).unwrap();
+            set_block_timestamp(3_000);
+            set_caller(accounts.alice);
+            contract.set_reputation_half_life(0).unwrap();
+            set_block_timestamp(10_000);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 2);
+            contract.set_reputation_half_life(1_000).unwrap();
+            set_block_timestamp(11_000);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 1);
+        }
+
+        #[ink::test]
+        fn decay_fraction_is_precise() {
+            let config = DecayConfig { since: 0, half_life: 4 };
+            let reputation: Reputation = 1_000_000_000_000;
+
+            // 2^(-1/4) = 0.840896415253...
+            assert_eq!(decay(reputation, config.halvings(1)), 840_896_415_253);
+            // 2^(-3/4) = 0.594603557501...
+            assert_eq!(decay(-reputation, config.halvings(3)), -594_603_557_500);
+            assert_eq!(decay(Reputation::MAX, config.halvings(4)), Reputation::MAX / 2);
+            assert_eq!(decay(Reputation::MIN, config.halvings(8)), Reputation::MIN / 4);
+            assert_eq!(decay(Reputation::MIN, config.halvings(0)), Reputation::MIN);
+        }
+
+        #[ink::test]
+        fn delegated_votes_can_be_spent_by_delegate() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+
+            set_caller(accounts.bob);
+            contract.delegate(accounts.charlie, 4).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+            assert_eq!(contract.delegated_out(accounts.bob), 4);
+            assert_eq!(contract.delegated_in(accounts.charlie), 4);
+
+            set_caller(accounts.charlie);
+            contract.vote(accounts.django, 12, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 0);
+            assert_eq!(contract.delegated_in(accounts.charlie), 2);
+            assert_eq!(contract.vote(accounts.django, 3, None), Err(Error::InsufficientVotes));
+
+            // Only the unspent part comes back.
+            set_caller(accounts.bob);
+            contract.revoke_delegation().unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 8);
+            assert_eq!(contract.delegated_in(accounts.charlie), 0);
+            assert_eq!(contract.delegation_of(accounts.bob), None);
+            assert_eq!(contract.revoke_delegation(), Err(Error::NoDelegation));
+            match recorded_events().last() {
+                Some(Event::DelegationRevoked(event)) => assert_eq!(event.refunded, 2),
+                _ => panic!("expected DelegationRevoked"),
+            }
+        }
+
+        #[ink::test]
+        fn delegation_chains_reject_cycles_and_excess_depth() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            contract.add_voter(accounts.eve, 10).unwrap();
+            contract.set_max_delegation_depth(2).unwrap();
+
+            set_caller(accounts.bob);
+            contract.delegate(accounts.charlie, 5).unwrap();
+            set_caller(accounts.charlie);
+            assert_eq!(contract.delegate(accounts.bob, 1), Err(Error::DelegationCycle));
+            contract.delegate(accounts.django, 12).unwrap();
+            assert_eq!(contract.delegated_in(accounts.charlie), 0);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 3);
+
+            set_caller(accounts.django);
+            assert_eq!(contract.delegate(accounts.bob, 1), Err(Error::DelegationCycle));
+            assert_eq!(contract.delegate(accounts.eve, 1), Err(Error::DelegationTooDeep));
+            set_caller(accounts.eve);
+            assert_eq!(contract.delegate(accounts.bob, 1), Err(Error::DelegationTooDeep));
+
+            set_caller(accounts.charlie);
+            assert_eq!(contract.delegate(accounts.eve, 1), Err(Error::DelegationExists));
+            assert_eq!(contract.delegate(accounts.charlie, 1), Err(Error::SelfDelegation));
+        }
+
+        #[ink::test]
+        fn delegations_are_refunded_separately() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            contract.add_voter(accounts.eve, 10).unwrap();
+
+            // Each revoker only gets back what is left of their own
+            // delegation, the most recent being spent first.
+            set_caller(accounts.bob);
+            contract.delegate(accounts.charlie, 4).unwrap();
+            set_caller(accounts.django);
+            contract.delegate(accounts.charlie, 4).unwrap();
+            set_caller(accounts.charlie);
+            contract.vote(accounts.eve, 15, None).unwrap();
+            set_caller(accounts.django);
+            contract.revoke_delegation().unwrap();
+            assert_eq!(contract.get_voter(accounts.django).unwrap().available_votes, 6);
+            set_caller(accounts.bob);
+            contract.revoke_delegation().unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 9);
+
+            // Retracting delegated spend refunds the delegation, not the
+            // delegate, or the delegator once the delegation is gone.
+            contract.delegate(accounts.charlie, 4).unwrap();
+            set_caller(accounts.charlie);
+            contract.retract_vote(accounts.eve, 15).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 10);
+            assert_eq!(contract.get_voter(accounts.django).unwrap().available_votes, 10);
+            assert_eq!(contract.delegated_in(accounts.charlie), 5);
+            contract.vote(accounts.django, 12, None).unwrap();
+            contract.retract_vote(accounts.django, 12).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 10);
+            assert_eq!(contract.delegated_in(accounts.charlie), 5);
+            set_caller(accounts.bob);
+            contract.revoke_delegation().unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 10);
+        }
+
+        #[ink::test]
+        fn revoking_returns_passed_on_votes_and_lowers_depth() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            contract.add_voter(accounts.eve, 10).unwrap();
+            contract.add_voter(accounts.frank, 10).unwrap();
+            contract.set_max_delegation_depth(2).unwrap();
+
+            set_caller(accounts.eve);
+            contract.delegate(accounts.bob, 2).unwrap();
+            set_caller(accounts.bob);
+            contract.delegate(accounts.charlie, 3).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 9);
+            set_caller(accounts.django);
+            contract.delegate(accounts.charlie, 1).unwrap();
+            set_caller(accounts.charlie);
+            assert_eq!(contract.delegate(accounts.frank, 1), Err(Error::DelegationTooDeep));
+
+            // The votes bob passed on from eve go back to eve's delegation,
+            // and charlie is left at the end of django's shorter chain.
+            set_caller(accounts.bob);
+            contract.revoke_delegation().unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 10);
+            assert_eq!(contract.delegated_in(accounts.bob), 2);
+            set_caller(accounts.charlie);
+            contract.delegate(accounts.frank, 1).unwrap();
+        }
+
+        #[ink::test]
+        fn removing_a_delegate_forfeits_delegated_votes() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            set_caller(accounts.bob);
+            contract.delegate(accounts.charlie, 4).unwrap();
+            set_caller(accounts.alice);
+            contract.remove_voter(accounts.charlie).unwrap();
+            assert_eq!(contract.delegated_in(accounts.charlie), 0);
+
+            set_caller(accounts.bob);
+            contract.revoke_delegation().unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+        }
+
+        #[ink::test]
+        fn roles_gate_privileged_messages() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            contract.grant_role(Role::Registrar, accounts.bob).unwrap();
+            contract.grant_role(Role::Moderator, accounts.charlie).unwrap();
+            assert!(contract.has_role(Role::Registrar, accounts.bob));
+            assert!(!contract.has_role(Role::Moderator, accounts.bob));
+            assert!(contract.has_role(Role::Pauser, accounts.alice));
+
+            set_caller(accounts.bob);
+            contract.add_voter(accounts.django, 1).unwrap();
+            assert_eq!(contract.remove_voter(accounts.django), Err(Error::MissingRole(Role::Moderator)));
+            assert_eq!(contract.grant_role(Role::Moderator, accounts.bob), Err(Error::MissingRole(Role::Admin)));
+
+            set_caller(accounts.charlie);
+            contract.remove_voter(accounts.django).unwrap();
+            assert_eq!(contract.add_voter(accounts.django, 1), Err(Error::MissingRole(Role::Registrar)));
+
+            set_caller(accounts.alice);
+            contract.revoke_role(Role::Registrar, accounts.bob).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(contract.add_voter(accounts.django, 1), Err(Error::MissingRole(Role::Registrar)));
+        }
+
+        #[ink::test]
+        fn admins_hold_every_role() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.grant_role(Role::Admin, accounts.eve).unwrap();
+
+            set_caller(accounts.eve);
+            contract.grant_role(Role::Registrar, accounts.frank).unwrap();
+            contract.add_voter(accounts.django, 1).unwrap();
+            contract.remove_voter(accounts.django).unwrap();
+            assert!(contract.has_role(Role::Moderator, accounts.eve));
+        }
+
+        #[ink::test]
+        fn role_changes_emit_events() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            contract.grant_role(Role::Pauser, accounts.bob).unwrap();
+            // Granting a held role again is a no-op.
+            contract.grant_role(Role::Pauser, accounts.bob).unwrap();
+            contract.revoke_role(Role::Pauser, accounts.bob).unwrap();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 5);
+            match &events[3] {
+                Event::RoleGranted(event) => {
+                    assert_eq!(event.role, Role::Pauser);
+                    assert_eq!(event.account, accounts.bob);
+                    assert_eq!(event.sender, accounts.alice);
+                }
+                _ => panic!("expected RoleGranted"),
+            }
+            match &events[4] {
+                Event::RoleRevoked(event) => assert_eq!(event.role, Role::Pauser),
+                _ => panic!("expected RoleRevoked"),
+            }
+        }
+
+        #[ink::test]
+        fn ownership_transfers_in_two_steps() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            contract.transfer_ownership(accounts.bob).unwrap();
+            assert_eq!(contract.owner(), Some(accounts.alice));
+            assert_eq!(contract.pending_owner(), Some(accounts.bob));
+
+            set_caller(accounts.charlie);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+            assert_eq!(contract.transfer_ownership(accounts.charlie), Err(Error::OnlyOwnerFunction));
+
+            set_caller(accounts.bob);
+            contract.accept_ownership().unwrap();
+            assert_eq!(contract.owner(), Some(accounts.bob));
+            assert_eq!(contract.pending_owner(), None);
+            assert!(contract.has_role(Role::Admin, accounts.bob));
+            assert!(!contract.has_role(Role::Admin, accounts.alice));
+
+            let events = recorded_events();
+            match &events[events.len() - 2] {
+                Event::OwnershipTransferStarted(event) => {
+                    assert_eq!(event.previous_owner, accounts.alice);
+                    assert_eq!(event.new_owner, accounts.bob);
+                }
+                _ => panic!("expected OwnershipTransferStarted"),
+            }
+            match &events[events.len() - 1] {
+                Event::OwnershipTransferred(event) => {
+                    assert_eq!(event.previous_owner, Some(accounts.alice));
+                    assert_eq!(event.new_owner, Some(accounts.bob));
+                }
+                _ => panic!("expected OwnershipTransferred"),
+            }
+        }
+
+        #[ink::test]
+        fn ownership_transfer_can_be_cancelled() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            assert_eq!(contract.cancel_ownership_transfer(), Err(Error::NoPendingOwnership));
+            contract.transfer_ownership(accounts.bob).unwrap();
+            contract.cancel_ownership_transfer().unwrap();
+            assert_eq!(contract.pending_owner(), None);
+            match recorded_events().last() {
+                Some(Event::OwnershipTransferCancelled(event)) => {
+                    assert_eq!(event.owner, accounts.alice);
+                    assert_eq!(event.pending_owner, accounts.bob);
+                }
+                _ => panic!("expected OwnershipTransferCancelled"),
+            }
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+        }
+
+        #[ink::test]
+        fn renounced_ownership_leaves_granted_roles() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.grant_role(Role::Admin, accounts.eve).unwrap();
+            contract.transfer_ownership(accounts.bob).unwrap();
+
+            contract.renounce_ownership().unwrap();
+            assert_eq!(contract.owner(), None);
+            assert_eq!(contract.pending_owner(), None);
+            assert_eq!(contract.add_voter(accounts.django, 1), Err(Error::MissingRole(Role::Registrar)));
+            assert_eq!(contract.renounce_ownership(), Err(Error::OnlyOwnerFunction));
+            match recorded_events().last() {
+                Some(Event::OwnershipTransferred(event)) => {
+                    assert_eq!(event.previous_owner, Some(accounts.alice));
+                    assert_eq!(event.new_owner, None);
+                }
+                _ => panic!("expected OwnershipTransferred"),
+            }
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+            set_caller(accounts.eve);
+            contract.add_voter(accounts.django, 1).unwrap();
+        }
+
+        fn contract_id() -> AccountId {
+            ink::env::test::callee::<ink::env::DefaultEnvironment>()
+        }
+
+        fn balance_of(account: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(account).unwrap()
+        }
+
+        /// Simulates `caller` sending `value` with the next call.
+        fn pay(caller: AccountId, value: Balance) {
+            set_caller(caller);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(value);
+            let contract_balance = balance_of(contract_id());
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract_id(), contract_balance + value);
+        }
+
+        #[ink::test]
+        fn register_locks_deposit_until_cooldown() {
+            let accounts = accounts();
+            let mut contract = setup();
+            pay(accounts.django, 100);
+            assert_eq!(contract.register(), Err(Error::RegistrationClosed));
+
+            set_caller(accounts.alice);
+            contract.set_registration_config(100, 7, 5).unwrap();
+            pay(accounts.django, 99);
+            assert_eq!(contract.register(), Err(Error::WrongDeposit));
+            pay(accounts.django, 100);
+            contract.register().unwrap();
+            assert_eq!(contract.get_voter(accounts.django).unwrap().available_votes, 7);
+            assert_eq!(contract.deposit_of(accounts.django), Some(Deposit { amount: 100, unlocks_at: 5, frozen: false }));
+            assert_eq!(contract.register(), Err(Error::VoterAlreadyRegistered));
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(contract.deregister(), Err(Error::CooldownActive));
+            advance_blocks(5);
+            let balance = balance_of(accounts.django);
+            contract.deregister().unwrap();
+            assert_eq!(balance_of(accounts.django), balance + 100);
+            assert_eq!(contract.get_voter(accounts.django), None);
+            assert_eq!(contract.deregister(), Err(Error::NoDeposit));
+            match recorded_events().last() {
+                Some(Event::DepositReleased(event)) => assert_eq!(event.amount, 100),
+                _ => panic!("expected DepositReleased"),
+            }
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.clear_registration_config(), Err(Error::MissingRole(Role::Admin)));
+            set_caller(accounts.alice);
+            contract.clear_registration_config().unwrap();
+            assert_eq!(contract.registration_config(), None);
+            pay(accounts.django, 100);
+            assert_eq!(contract.register(), Err(Error::RegistrationClosed));
+        }
+
+        #[ink::test]
+        fn admin_can_seize_deposits() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_registration_config(50, 1, 0).unwrap();
+            pay(accounts.django, 50);
+            contract.register().unwrap();
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.seize_deposit(accounts.django), Err(Error::MissingRole(Role::Admin)));
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.seize_deposit(accounts.django), Err(Error::NoTreasury));
+            contract.set_treasury(accounts.eve).unwrap();
+            assert_eq!(contract.treasury(), Some(accounts.eve));
+            let treasury_balance = balance_of(accounts.eve);
+            contract.seize_deposit(accounts.django).unwrap();
+            assert_eq!(balance_of(accounts.eve), treasury_balance + 50);
+            assert_eq!(contract.get_voter(accounts.django), None);
+            match recorded_events().last() {
+                Some(Event::DepositSeized(event)) => assert_eq!(event.treasury, accounts.eve),
+                _ => panic!("expected DepositSeized"),
+            }
+
+            set_caller(accounts.django);
+            assert_eq!(contract.deregister(), Err(Error::NoDeposit));
+        }
+
+        #[ink::test]
+        fn removed_voter_deposit_is_frozen_for_seizure() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_registration_config(50, 1, 0).unwrap();
+            pay(accounts.django, 50);
+            contract.register().unwrap();
+
+            set_caller(accounts.alice);
+            contract.remove_voter(accounts.django).unwrap();
+            assert!(contract.deposit_of(accounts.django).unwrap().frozen);
+
+            pay(accounts.django, 50);
+            assert_eq!(contract.register(), Err(Error::DepositPending));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(contract.deregister(), Err(Error::DepositFrozen));
+
+            set_caller(accounts.alice);
+            contract.set_treasury(accounts.eve).unwrap();
+            contract.seize_deposit(accounts.django).unwrap();
+            assert_eq!(contract.deposit_of(accounts.django), None);
+        }
+
+        #[ink::test]
+        fn add_voters_and_remove_voters_in_batches() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            contract.add_voters(vec![(accounts.django, 1), (accounts.eve, 2)]).unwrap();
+            assert_eq!(contract.voter_count(), 4);
+            assert_eq!(
+                contract.add_voters(vec![(accounts.frank, 1), (accounts.bob, 1)]),
+                Err(Error::BatchEntryFailed(1, Box::new(Error::VoterAlreadyRegistered)))
+            );
+
+            contract.remove_voters(vec![accounts.django, accounts.eve]).unwrap();
+            assert_eq!(contract.get_voter(accounts.eve), None);
+            assert_eq!(
+                contract.remove_voters(vec![accounts.bob, accounts.django]),
+                Err(Error::BatchEntryFailed(1, Box::new(Error::UnregisteredVoter)))
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.add_voters(vec![]), Err(Error::MissingRole(Role::Registrar)));
+        }
+
+        #[ink::test]
+        fn vote_many_applies_entries_in_order() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote_many(vec![(accounts.charlie, 3, None), (accounts.django, -2, None), (accounts.charlie, 1, None)]).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 4);
+            assert_eq!(contract.get_voter(accounts.django).unwrap().reputation, -2);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 4);
+
+            assert_eq!(
+                contract.vote_many(vec![(accounts.charlie, 1, None), (accounts.bob, 1, None)]),
+                Err(Error::BatchEntryFailed(1, Box::new(Error::VoterEqualToCandidate)))
+            );
+        }
+
+        #[ink::test]
+        fn batches_respect_max_batch_size() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_max_batch_size(1).unwrap();
+
+            assert_eq!(
+                contract.add_voters(vec![(accounts.django, 1), (accounts.eve, 1)]),
+                Err(Error::BatchTooLarge)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.vote_many(vec![(accounts.charlie, 1, None), (accounts.charlie, 1, None)]),
+                Err(Error::BatchTooLarge)
+            );
+            assert_eq!(contract.set_max_batch_size(10), Err(Error::MissingRole(Role::Admin)));
+        }
+
+        fn addresses(voters: Vec<Voter>) -> Vec<AccountId> {
+            voters.into_iter().map(|voter| voter.address).collect()
+        }
+
+        /// Registers Django, Eve and Frank too and gives everyone a distinct
+        /// reputation: Charlie 5, Django 3, Bob 0, Eve -2, Frank -4.
+        fn ranked_setup() -> OptionsAndFutures {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voters(vec![(accounts.django, 10), (accounts.eve, 10), (accounts.frank, 10)]).unwrap();
+            set_caller(accounts.bob);
+            contract.vote_many(vec![(accounts.charlie, 5, None), (accounts.eve, -2, None), (accounts.frank, -1, None)]).unwrap();
+            set_caller(accounts.charlie);
+            contract.vote_many(vec![(accounts.django, 3, None), (accounts.frank, -3, None)]).unwrap();
+            contract
+        }
+
+        #[ink::test]
+        fn ranking_orders_voters_by_reputation() {
+            let accounts = accounts();
+            let contract = ranked_setup();
+
+            assert_eq!(
+                addresses(contract.top(10)),
+                vec![accounts.charlie, accounts.django, accounts.bob, accounts.eve, accounts.frank]
+            );
+            assert_eq!(addresses(contract.top(2)), vec![accounts.charlie, accounts.django]);
+            assert_eq!(addresses(contract.bottom(2)), vec![accounts.frank, accounts.eve]);
+            assert_eq!(contract.rank_of(accounts.charlie), Some(1));
+            assert_eq!(contract.rank_of(accounts.eve), Some(4));
+            assert_eq!(contract.rank_of(accounts.alice), None);
+            assert_eq!(addresses(contract.voters_in_range(-2, 3, 0, 10)), vec![accounts.django, accounts.bob, accounts.eve]);
+            assert_eq!(addresses(contract.voters_in_range(-2, 3, 1, 1)), vec![accounts.bob]);
+            assert!(contract.voters_in_range(6, 10, 0, 10).is_empty());
+        }
+
+        #[ink::test]
+        fn ranking_follows_votes_and_removals() {
+            let accounts = accounts();
+            let mut contract = ranked_setup();
+
+            // Frank climbs from the bottom to the top.
+            set_caller(accounts.django);
+            contract.vote(accounts.frank, 10, None).unwrap();
+            assert_eq!(contract.rank_of(accounts.frank), Some(1));
+            // Charlie drops to the bottom.
+            set_caller(accounts.eve);
+            contract.vote(accounts.charlie, -10, None).unwrap();
+            assert_eq!(
+                addresses(contract.top(10)),
+                vec![accounts.frank, accounts.django, accounts.bob, accounts.eve, accounts.charlie]
+            );
+
+            set_caller(accounts.alice);
+            contract.remove_voters(vec![accounts.frank, accounts.charlie]).unwrap();
+            assert_eq!(addresses(contract.top(10)), vec![accounts.django, accounts.bob, accounts.eve]);
+            assert_eq!(addresses(contract.bottom(10)), vec![accounts.eve, accounts.bob, accounts.django]);
+        }
+
+        #[ink::test]
+        fn ranking_stays_consistent_through_many_updates() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.unrank(accounts.bob);
+            contract.unrank(accounts.charlie);
+            let account = |seed: u8| AccountId::from([seed; 32]);
+            let mut expected: Vec<(Reputation, AccountId)> = Vec::new();
+            let mut state: u64 = 7;
+            for round in 0..200u32 {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                let seed = (state >> 33) as u8 % 60 + 10;
+                let reputation = (state >> 40) as Reputation % 41 - 20;
+                expected.retain(|&(_, other)| other != account(seed));
+                if round % 7 == 0 {
+                    contract.unrank(account(seed));
+                } else {
+                    contract.rerank(account(seed), rank_key(reputation, 0));
+                    expected.push((reputation, account(seed)));
+                }
+            }
+            expected.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+            let mut walked = Vec::new();
+            let mut cursor = contract.rank_link(None, 0).next;
+            while let Some(current) = cursor {
+                walked.push(current);
+                cursor = contract.rank_link(Some(current), 0).next;
+            }
+            assert_eq!(walked, expected.iter().map(|&(_, account)| account).collect::<Vec<_>>());
+            for (position, &(_, account)) in (1..).zip(&expected) {
+                assert_eq!(contract.rank_of(account), Some(position));
+                assert_eq!(contract.ranked_at(position), Some(account));
+            }
+            assert_eq!(contract.ranked_at(expected.len() as u32 + 1), None);
+            assert_eq!(contract.ranking_tail.get_or_default(), expected.last().map(|&(_, account)| account));
+        }
+
+        #[ink::test]
+        fn ranking_follows_decay() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            contract.set_reputation_half_life(1_000).unwrap();
+
+            // Charlie's 8 has halved by the time Django gets 5.
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 8, None).unwrap();
+            set_block_timestamp(1_000);
+            set_caller(accounts.charlie);
+            contract.vote(accounts.django, 5, None).unwrap();
+            assert_eq!(addresses(contract.top(2)), vec![accounts.django, accounts.charlie]);
+            assert_eq!(addresses(contract.voters_in_range(4, 4, 0, 10)), vec![accounts.charlie]);
+            assert_eq!(contract.rank_of(accounts.charlie), Some(2));
+        }
+
+        #[ink::test]
+        fn ranking_separates_huge_reputations() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.unrank(accounts.bob);
+            contract.unrank(accounts.charlie);
+            let account = |seed: u8| AccountId::from([seed; 32]);
+            // Higher reputations go to higher addresses, so ties broken by
+            // address alone would come out backwards.
+            let reputations = [i128::MIN, i128::MIN + 1, -(1 << 100), 1 << 63, 1 << 100, (1 << 100) + 1, i128::MAX - 1, i128::MAX];
+            for (seed, &reputation) in (10..).zip(&reputations) {
+                contract.rerank(account(seed), rank_key(reputation, 0));
+            }
+            for (position, seed) in (1..).zip((10..18).rev()) {
+                assert_eq!(contract.ranked_at(position), Some(account(seed)));
+            }
+        }
+
+        /// Values at and around the edges of the `i128` range.
+        const EXTREME_VOTES: [i128; 9] = [i128::MIN, i128::MIN + 1, -(1 << 64), -1, 0, 1, 1 << 64, i128::MAX - 1, i128::MAX];
+
+        #[ink::test]
+        fn vote_never_panics_on_extreme_values() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut contract = OptionsAndFutures::new();
+
+            // Every case gets a fresh voter and candidate pair.
+            let mut seed: u8 = 0;
+            for &first in EXTREME_VOTES.iter() {
+                for &second in EXTREME_VOTES.iter() {
+                    let voter = AccountId::from([seed; 32]);
+                    let candidate = AccountId::from([seed + 1; 32]);
+                    seed += 2;
+                    set_caller(accounts.alice);
+                    contract.add_voters(vec![(voter, u128::MAX), (candidate, u128::MAX)]).unwrap();
+                    set_caller(voter);
+
+                    let mut expected: Reputation = 0;
+                    let mut spent: u128 = 0;
+                    for votes in [first, second] {
+                        let result = contract.vote(candidate, votes, None);
+                        match expected.checked_add(votes) {
+                            _ if votes == 0 => assert_eq!(result, Err(Error::ZeroVotes)),
+                            _ if spent.checked_add(votes.unsigned_abs()).is_none() => {
+                                assert_eq!(result, Err(Error::InsufficientVotes))
+                            }
+                            None => assert_eq!(result, Err(Error::ReputationOverflow)),
+                            Some(reputation) => {
+                                assert_eq!(result, Ok(()));
+                                expected = reputation;
+                                spent += votes.unsigned_abs();
+                            }
+                        }
+                    }
+
+                    assert_eq!(contract.get_voter(candidate).unwrap().reputation, expected);
+                    assert_eq!(contract.get_voter(voter).unwrap().available_votes, u128::MAX - spent);
+                    assert_eq!(
+                        contract.vote_between(voter, candidate).map(|record| record.net),
+                        (spent > 0).then_some(expected)
+                    );
+                }
+            }
+        }
+
+        #[ink::test]
+        fn retract_handles_extreme_values() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, u128::MAX).unwrap();
+
+            set_caller(accounts.django);
+            contract.vote(accounts.charlie, i128::MIN, None).unwrap();
+            assert_eq!(contract.retract_vote(accounts.charlie, 0), Err(Error::ZeroVotes));
+            assert_eq!(contract.retract_vote(accounts.charlie, i128::MIN.unsigned_abs() + 1), Err(Error::RetractExceedsVotes));
+            contract.retract_vote(accounts.charlie, i128::MIN.unsigned_abs()).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 0);
+            assert_eq!(contract.get_voter(accounts.django).unwrap().available_votes, u128::MAX);
+        }
+
+        #[ink::test]
+        fn failed_overflowing_vote_leaves_state_untouched() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, u128::MAX).unwrap();
+
+            set_caller(accounts.django);
+            contract.vote(accounts.charlie, i128::MAX, None).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(contract.vote(accounts.charlie, 1, None), Err(Error::ReputationOverflow));
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 10);
+            assert_eq!(contract.vote_between(accounts.bob, accounts.charlie), None);
+            assert_eq!(contract.delegate(accounts.charlie, 0), Err(Error::ZeroVotes));
+            assert_eq!(contract.delegate(accounts.charlie, 11), Err(Error::InsufficientVotes));
+        }
+
+        #[ink::test]
+        fn candidate_epoch_cap_limits_votes_per_epoch() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_epoch_config(10, 10, RefillMode::Reset).unwrap();
+            contract.set_collusion_rules(Some(4), None, 0, ReciprocalPolicy::Allow).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, None).unwrap();
+            assert_eq!(contract.vote(accounts.charlie, -2, None), Err(Error::CandidateEpochCapExceeded));
+            contract.vote(accounts.charlie, -1, None).unwrap();
+
+            advance_blocks(10);
+            contract.vote(accounts.charlie, 4, None).unwrap();
+        }
+
+        #[ink::test]
+        fn candidate_share_limits_concentration() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            contract.set_collusion_rules(None, Some(5_000), 0, ReciprocalPolicy::Allow).unwrap();
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.vote(accounts.charlie, 6, None), Err(Error::CandidateShareExceeded));
+            contract.vote(accounts.charlie, 5, None).unwrap();
+            // Spent votes still count towards the budget.
+            assert_eq!(contract.vote(accounts.charlie, 1, None), Err(Error::CandidateShareExceeded));
+            contract.vote(accounts.django, 5, None).unwrap();
+        }
+
+        #[ink::test]
+        fn reciprocal_votes_can_be_blocked() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_collusion_rules(None, None, 5, ReciprocalPolicy::Block).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 2, None).unwrap();
+            set_caller(accounts.charlie);
+            assert_eq!(contract.vote(accounts.bob, 1, None), Err(Error::ReciprocalVoteBlocked));
+            // Downvotes are never reciprocal.
+            contract.vote(accounts.bob, -1, None).unwrap();
+
+            advance_blocks(6);
+            contract.vote(accounts.bob, 1, None).unwrap();
+        }
+
+        #[ink::test]
+        fn reciprocal_votes_can_be_discounted() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_collusion_rules(None, None, 5, ReciprocalPolicy::Discount(2_500)).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 2, None).unwrap();
+            set_caller(accounts.charlie);
+            contract.vote(accounts.bob, 8, None).unwrap();
+
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, 2);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 2);
+            assert_eq!(
+                contract.vote_between(accounts.charlie, accounts.bob),
+                Some(VoteRecord {
+                    net: 8,
+                    total_cast: 8,
+                    effect: 2,
+                    decay_clock: 0,
+                    epoch: 0,
+                    refundable: 8,
+                    removals: (0, 0),
+                    delegated: Vec::new(),
+                    categories: Vec::new(),
+                    last_block: 0,
+                })
+            );
+
+            // Retracting refunds what was paid and takes back the discounted
+            // effect.
+            contract.retract_vote(accounts.bob, 4).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, 1);
+            contract.retract_vote(accounts.bob, 4).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, 0);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 10);
+        }
+
+        #[ink::test]
+        fn collusion_rules_are_validated() {
+            let accounts = accounts();
+            let mut contract = setup();
+
+            assert_eq!(
+                contract.set_collusion_rules(None, Some(10_001), 0, ReciprocalPolicy::Allow),
+                Err(Error::InvalidConfig)
+            );
+            assert_eq!(
+                contract.set_collusion_rules(None, None, 0, ReciprocalPolicy::Discount(10_001)),
+                Err(Error::InvalidConfig)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_collusion_rules(None, None, 0, ReciprocalPolicy::Allow),
+                Err(Error::MissingRole(Role::Admin))
+            );
+        }
+
+        #[ink::test]
+        fn vouched_applicant_becomes_voter() {
+            let accounts = accounts();
+            let mut contract = ranked_setup();
+            set_caller(accounts.eve);
+            assert_eq!(contract.request_membership(), Err(Error::VouchingClosed));
+
+            set_caller(accounts.alice);
+            contract.set_vouching_config(2, 3, 1, 4).unwrap();
+            set_caller(accounts.eve);
+            assert_eq!(contract.request_membership(), Err(Error::VoterAlreadyRegistered));
+
+            let applicant = AccountId::from([0x42; 32]);
+            set_caller(applicant);
+            contract.request_membership().unwrap();
+            assert_eq!(contract.request_membership(), Err(Error::MembershipAlreadyRequested));
+
+            // Bob's reputation of 0 is below the threshold.
+            set_caller(accounts.bob);
+            assert_eq!(contract.vouch(applicant), Err(Error::ReputationTooLow));
+            set_caller(accounts.charlie);
+            contract.vouch(applicant).unwrap();
+            assert_eq!(contract.vouch(applicant), Err(Error::AlreadyVouched));
+            assert_eq!(contract.get_voter(applicant), None);
+            assert_eq!(contract.membership_request(applicant), Some(vec![accounts.charlie]));
+
+            set_caller(accounts.django);
+            contract.vouch(applicant).unwrap();
+            assert_eq!(contract.get_voter(applicant).unwrap().available_votes, 4);
+            assert_eq!(contract.membership_request(applicant), None);
+            assert_eq!(contract.vouchers_of(applicant), vec![accounts.charlie, accounts.django]);
+            assert_eq!(contract.vouch(applicant), Err(Error::NoMembershipRequest));
+        }
+
+        #[ink::test]
+        fn lapsed_vouches_do_not_count() {
+            let accounts = accounts();
+            let mut contract = ranked_setup();
+            set_caller(accounts.alice);
+            contract.set_vouching_config(2, 3, 1, 4).unwrap();
+
+            let applicant = AccountId::from([0x42; 32]);
+            set_caller(applicant);
+            contract.request_membership().unwrap();
+            set_caller(accounts.charlie);
+            contract.vouch(applicant).unwrap();
+
+            // Charlie drops below the threshold before the second vouch.
+            set_caller(accounts.eve);
+            contract.vote(accounts.charlie, -3, None).unwrap();
+            set_caller(accounts.django);
+            contract.vouch(applicant).unwrap();
+            assert_eq!(contract.get_voter(applicant), None);
+            assert_eq!(contract.membership_request(applicant), Some(vec![accounts.django]));
+
+            // Adding the applicant directly clears the application.
+            set_caller(accounts.alice);
+            contract.add_voter(applicant, 1).unwrap();
+            assert_eq!(contract.membership_request(applicant), None);
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.clear_vouching_config(), Err(Error::MissingRole(Role::Admin)));
+            set_caller(accounts.alice);
+            contract.clear_vouching_config().unwrap();
+            assert_eq!(contract.vouching_config(), None);
+            set_caller(AccountId::from([0x43; 32]));
+            assert_eq!(contract.request_membership(), Err(Error::VouchingClosed));
+        }
+
+        #[ink::test]
+        fn voucher_penalty_is_fixed_at_admission() {
+            let accounts = accounts();
+            let mut contract = ranked_setup();
+            set_caller(accounts.alice);
+            contract.set_vouching_config(2, 3, 2, 4).unwrap();
+
+            let applicant = AccountId::from([0x42; 32]);
+            set_caller(applicant);
+            contract.request_membership().unwrap();
+            set_caller(accounts.charlie);
+            contract.vouch(applicant).unwrap();
+            set_caller(accounts.django);
+            contract.vouch(applicant).unwrap();
+
+            // Neither a later penalty nor closing vouching changes what the
+            // vouchers owe.
+            set_caller(accounts.alice);
+            contract.set_vouching_config(2, 3, 5, 4).unwrap();
+            contract.clear_vouching_config().unwrap();
+            contract.remove_voter(applicant).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 3);
+            assert_eq!(contract.get_voter(accounts.django).unwrap().reputation, 1);
+        }
+
+        #[ink::test]
+        fn removing_vouched_member_penalises_vouchers() {
+            let accounts = accounts();
+            let mut contract = ranked_setup();
+            set_caller(accounts.alice);
+            contract.set_vouching_config(2, 3, 2, 4).unwrap();
+
+            let applicant = AccountId::from([0x42; 32]);
+            set_caller(applicant);
+            contract.request_membership().unwrap();
+            set_caller(accounts.charlie);
+            contract.vouch(applicant).unwrap();
+            set_caller(accounts.django);
+            contract.vouch(applicant).unwrap();
+
+            set_caller(accounts.alice);
+            contract.remove_voter(applicant).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 3);
+            assert_eq!(contract.get_voter(accounts.django).unwrap().reputation, 1);
+            assert_eq!(contract.vouchers_of(applicant), Vec::<AccountId>::new());
+            match &recorded_events()[..] {
+                [.., Event::VoucherPenalised(first), Event::VoucherPenalised(second), Event::VoterRemoved(_)] => {
+                    assert_eq!((first.voucher, first.reputation), (accounts.charlie, 3));
+                    assert_eq!((second.voucher, second.penalty), (accounts.django, 2));
+                }
+                _ => panic!("expected VoucherPenalised events"),
+            }
+        }
+
+        #[ink::test]
+        fn pause_blocks_state_changes_but_not_queries() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.grant_role(Role::Pauser, accounts.eve).unwrap();
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.pause(None, None), Err(Error::MissingRole(Role::Pauser)));
+            set_caller(accounts.eve);
+            contract.pause(None, None).unwrap();
+            assert!(contract.is_paused(Operation::Voting));
+            assert!(contract.is_paused(Operation::Registration));
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.vote(accounts.charlie, 1, None), Err(Error::OperationPaused(Operation::Voting)));
+            assert_eq!(contract.delegate(accounts.charlie, 1), Err(Error::OperationPaused(Operation::Delegation)));
+            set_caller(accounts.alice);
+            assert_eq!(contract.add_voter(accounts.django, 5), Err(Error::OperationPaused(Operation::Registration)));
+            assert_eq!(contract.remove_voter(accounts.bob), Err(Error::OperationPaused(Operation::Removal)));
+            // Queries and configuration keep working.
+            assert_eq!(contract.voter_count(), 2);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 10);
+            contract.set_max_batch_size(10).unwrap();
+
+            set_caller(accounts.eve);
+            contract.unpause(None).unwrap();
+            assert_eq!(contract.unpause(None), Err(Error::NotPaused));
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 1, None).unwrap();
+
+            let events = recorded_events();
+            match &events[events.len() - 3] {
+                Event::Paused(event) => {
+                    assert_eq!(event.operation, None);
+                    assert_eq!(event.until, None);
+                    assert_eq!(event.account, accounts.eve);
+                }
+                _ => panic!("expected Paused"),
+            }
+            match &events[events.len() - 2] {
+                Event::Unpaused(event) => {
+                    assert_eq!(event.operation, None);
+                    assert_eq!(event.account, accounts.eve);
+                }
+                _ => panic!("expected Unpaused"),
+            }
+        }
+
+        #[ink::test]
+        fn operations_pause_separately_and_auto_unpause() {
+            let accounts = accounts();
+            let mut contract = setup();
+            let block = ink::env::block_number::<ink::env::DefaultEnvironment>();
+            assert_eq!(contract.pause(Some(Operation::Voting), Some(block)), Err(Error::InvalidConfig));
+            contract.pause(Some(Operation::Voting), Some(block + 5)).unwrap();
+            assert_eq!(
+                contract.pause_state(Some(Operation::Voting)),
+                Some(PauseState { paused_at: block, until: Some(block + 5) })
+            );
+            assert_eq!(contract.pause_state(None), None);
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.vote(accounts.charlie, 1, None), Err(Error::OperationPaused(Operation::Voting)));
+            contract.delegate(accounts.charlie, 1).unwrap();
+
+            advance_blocks(4);
+            assert_eq!(contract.vote(accounts.charlie, 1, None), Err(Error::OperationPaused(Operation::Voting)));
+            advance_blocks(1);
+            assert!(!contract.is_paused(Operation::Voting));
+            contract.vote(accounts.charlie, 1, None).unwrap();
+
+            // A paused operation stays blocked while the whole contract is
+            // paused, and lifting the expired pause is an error.
+            set_caller(accounts.alice);
+            assert_eq!(contract.unpause(Some(Operation::Voting)), Err(Error::NotPaused));
+            contract.pause(None, None).unwrap();
+            contract.pause(Some(Operation::Delegation), None).unwrap();
+            contract.unpause(None).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 1, None).unwrap();
+            assert_eq!(contract.revoke_delegation(), Err(Error::OperationPaused(Operation::Delegation)));
+        }
+
+        /// Writes storage as the version 1 contract left it, with `registry`
+        /// as its list of voter addresses, and loads the contract from it.
+        fn load_v1_storage(owner: AccountId, registry: Vec<AccountId>, voters: &[VoterV1]) -> OptionsAndFutures {
+            ink::env::set_contract_storage(&0u32, &(registry, owner));
+            let voters_key = ink::primitives::KeyComposer::compute_key("OptionsAndFutures", "", "voters").unwrap();
+            for voter in voters {
+                ink::env::set_contract_storage(&(voters_key, voter.address), voter);
+            }
+            ink::env::get_contract_storage(&0u32).unwrap().unwrap()
+        }
+
+        #[ink::test]
+        fn migrate_converts_v1_storage_keeping_reputation() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let v1 = |address, reputation, available_votes| VoterV1 { reputation, address, available_votes };
+            // Version 1 could list a voter twice and kept removed voters in
+            // its registry.
+            let registry = vec![
+                accounts.bob,
+                accounts.charlie,
+                accounts.eve,
+                accounts.frank,
+                accounts.bob,
+                accounts.django,
+            ];
+            let voters = [
+                v1(accounts.bob, 3, 8),
+                v1(accounts.charlie, -2, 10),
+                v1(accounts.eve, 5, 0),
+                v1(accounts.frank, 1, 0),
+            ];
+            let mut contract = load_v1_storage(accounts.alice, registry, &voters);
+            assert_eq!(contract.storage_version(), 1);
+            assert_eq!(contract.owner(), Some(accounts.alice));
+            let upgraded = ink::env::block_number::<ink::env::DefaultEnvironment>();
+            advance_blocks(1);
+
+            // Version 1 voters read correctly before they are migrated.
+            let bob = contract.get_voter(accounts.bob).unwrap();
+            assert_eq!((bob.reputation, bob.available_votes, bob.registered_at), (3, 8, None));
+
+            // Writing a voter converts it on the spot, and removing an
+            // unconverted voter works.
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 1, None).unwrap();
+            assert!(!contract.voters.contains(accounts.bob));
+            assert_eq!(contract.voters_v2.get(accounts.charlie).unwrap().reputation, -1);
+            assert_eq!(contract.voter_count(), 2);
+            set_caller(accounts.alice);
+            contract.remove_voter(accounts.eve).unwrap();
+            assert_eq!(contract.get_voter(accounts.eve), None);
+            advance_blocks(1);
+
+            // Converted or not, version 1 voters keep their weight at past
+            // blocks, while the total is incomplete until `migrate` is done.
+            assert_eq!(contract.reputation_at(accounts.bob, upgraded), Ok(3));
+            assert_eq!(contract.reputation_at(accounts.frank, upgraded + 1), Ok(1));
+            assert_eq!(contract.total_reputation_at(upgraded + 1), Err(Error::MigrationPending));
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.migrate(1), Err(Error::MissingRole(Role::Admin)));
+            set_caller(accounts.alice);
+            assert_eq!(contract.migrate(2), Ok(4));
+            assert_eq!(contract.storage_version(), 1);
+            assert_eq!(contract.migrate(10), Ok(0));
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+            assert_eq!(contract.migrate(10), Ok(0));
+            advance_blocks(1);
+            assert_eq!(contract.total_reputation_at(upgraded + 1), Err(Error::MigrationPending));
+            assert_eq!(contract.total_reputation_at(upgraded + 2), Ok(3));
+            assert_eq!(contract.reputation_at(accounts.frank, upgraded + 1), Ok(1));
+
+            // The root cell still holds nothing but the version 1 fields.
+            let mut root = Vec::new();
+            ink::storage::traits::Storable::encode(&contract, &mut root);
+            assert_eq!(root, scale::Encode::encode(&(Vec::<AccountId>::new(), accounts.alice)));
+            assert!(!contract.voters.contains(accounts.frank));
+            assert_eq!(contract.voter_count(), 3);
+            let reputations: Vec<_> = contract.top(3).iter().map(|voter| voter.reputation).collect();
+            assert_eq!(reputations, vec![3, 1, -1]);
+            assert_eq!(contract.total_reputation(), 3);
+            match recorded_events().last().unwrap() {
+                Event::StorageMigrated(event) => assert_eq!(event.version, STORAGE_VERSION),
+                _ => panic!("expected StorageMigrated"),
+            }
+        }
+
+        #[ink::test]
+        fn only_owner_can_set_code() {
+            let accounts = accounts();
+            let mut contract = setup();
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_code([0x01; 32]), Err(Error::OnlyOwnerFunction));
+        }
+
+        #[ink::test]
+        fn reputation_checkpoints_answer_past_blocks() {
+            let accounts = accounts();
+            let mut contract = setup();
+            let start = ink::env::block_number::<ink::env::DefaultEnvironment>();
+
+            advance_blocks(1);
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, None).unwrap();
+            advance_blocks(1);
+            // Several changes within a block leave a single checkpoint.
+            contract.vote(accounts.charlie, 1, None).unwrap();
+            contract.vote(accounts.charlie, 1, None).unwrap();
+            set_caller(accounts.charlie);
+            contract.vote(accounts.bob, -2, None).unwrap();
+            assert_eq!(contract.reputation_at(accounts.charlie, start + 2), Err(Error::BlockNotPast));
+            assert_eq!(contract.total_reputation_at(start + 3), Err(Error::BlockNotPast));
+            assert_eq!(contract.checkpoint_counts.get(Some(accounts.charlie)), Some(3));
+
+            advance_blocks(1);
+            set_caller(accounts.alice);
+            contract.remove_voter(accounts.charlie).unwrap();
+            advance_blocks(1);
+
+            let history: Vec<_> = (start..start + 4)
+                .map(|block| contract.reputation_at(accounts.charlie, block).unwrap())
+                .collect();
+            assert_eq!(history, vec![0, 3, 5, 0]);
+            assert_eq!(contract.reputation_at(accounts.bob, start + 2), Ok(-2));
+            assert_eq!(contract.reputation_at(accounts.django, start + 2), Ok(0));
+            let totals: Vec<_> = (start..start + 4)
+                .map(|block| contract.total_reputation_at(block).unwrap())
+                .collect();
+            assert_eq!(totals, vec![0, 3, 3, -2]);
+            assert_eq!(contract.total_reputation(), -2);
+        }
+
+        #[ink::test]
+        fn reputation_checkpoints_include_decay() {
+            let accounts = accounts();
+            let mut contract = setup();
+            set_block_timestamp(0);
+            contract.set_reputation_half_life(1_000).unwrap();
+
+            advance_blocks(1);
+            set_block_timestamp(0);
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 8, None).unwrap();
+            let voted = ink::env::block_number::<ink::env::DefaultEnvironment>();
+
+            // Charlie's 8 has halved by the time Bob gets 4, though it was
+            // never written again.
+            advance_blocks(1);
+            set_block_timestamp(1_000);
+            set_caller(accounts.charlie);
+            contract.vote(accounts.bob, 4, None).unwrap();
+            advance_blocks(1);
+            set_block_timestamp(1_000);
+
+            assert_eq!(contract.reputation_at(accounts.charlie, voted), Ok(8));
+            assert_eq!(contract.reputation_at(accounts.charlie, voted + 1), Ok(4));
+            assert_eq!(contract.reputation_at(accounts.bob, voted + 1), Ok(4));
+            assert_eq!(contract.total_reputation_at(voted), Ok(8));
+            assert_eq!(contract.total_reputation_at(voted + 1), Ok(8));
+            assert_eq!(contract.total_reputation(), 8);
+        }
+
+        #[ink::test]
+        fn passed_proposal_executes_its_action() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, None).unwrap();
+            contract.vote(accounts.django, 2, None).unwrap();
+            set_caller(accounts.charlie);
+            let description_hash = Hash::from([0x07; 32]);
+            assert_eq!(contract.propose(description_hash, None), Err(Error::GovernanceClosed));
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_governance_config(0, 4, 5_000), Err(Error::InvalidConfig));
+            contract.set_governance_config(5, 4, 5_000).unwrap();
+            assert_eq!(contract.propose(description_hash, None), Err(Error::UnregisteredVoter));
+
+            advance_blocks(1);
+            set_caller(accounts.charlie);
+            let call = |selector: [u8; 4], args: &[u8]| Some([&selector[..], args].concat());
+            let max_batch_size = call(ink::selector_bytes!("set_max_batch_size"), &scale::Encode::encode(&7u32));
+            assert_eq!(
+                contract.propose(description_hash, call(ink::selector_bytes!("set_code"), &[0; 32])),
+                Err(Error::UnsupportedCall)
+            );
+            assert_eq!(
+                contract.propose(description_hash, call(ink::selector_bytes!("set_max_batch_size"), &[7, 0])),
+                Err(Error::UnsupportedCall)
+            );
+            let dispute_config = scale::Encode::encode(&(0 as Balance, 0u32, 5 as BlockNumber));
+            assert_eq!(
+                contract.propose(description_hash, call(ink::selector_bytes!("set_dispute_config"), &dispute_config)),
+                Err(Error::InvalidConfig)
+            );
+            let id = contract.propose(description_hash, max_batch_size).unwrap();
+            assert_eq!(contract.proposal(id).unwrap().action, Some(ProposalAction::MaxBatchSize(7)));
+            assert_eq!(contract.proposal_state(id), Some(ProposalState::Pending));
+            assert_eq!(contract.cast_ballot(id, Ballot::Yes), Err(Error::VotingNotOpen));
+
+            // Reputation gained in the proposal block, after the snapshot,
+            // does not add weight.
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 5, None).unwrap();
+            advance_blocks(1);
+            set_caller(accounts.charlie);
+            contract.cast_ballot(id, Ballot::Yes).unwrap();
+            assert_eq!(contract.cast_ballot(id, Ballot::No), Err(Error::AlreadyVoted));
+            set_caller(accounts.django);
+            contract.cast_ballot(id, Ballot::No).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(contract.cast_ballot(id, Ballot::Yes), Err(Error::NoVotingWeight));
+            assert_eq!(contract.execute(id), Err(Error::VotingNotEnded));
+
+            let proposal = contract.proposal(id).unwrap();
+            assert_eq!((proposal.yes, proposal.no, proposal.abstain), (3, 2, 0));
+            assert_eq!(contract.ballot_of(id, accounts.django), Some(Ballot::No));
+
+            advance_blocks(5);
+            assert_eq!(contract.cast_ballot(id, Ballot::Yes), Err(Error::VotingNotOpen));
+            assert_eq!(contract.proposal_state(id), Some(ProposalState::Succeeded));
+            contract.execute(id).unwrap();
+            assert_eq!(contract.max_batch_size(), 7);
+            assert_eq!(contract.proposal_state(id), Some(ProposalState::Executed));
+            assert_eq!(contract.execute(id), Err(Error::AlreadyExecuted));
+            match recorded_events().last().unwrap() {
+                Event::ProposalExecuted(event) => assert_eq!(event.proposal_id, id),
+                _ => panic!("expected ProposalExecuted"),
+            }
+        }
+
+        #[ink::test]
+        fn proposals_need_quorum_and_threshold() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_governance_config(3, 4, 6_000).unwrap();
+            contract.add_voter(accounts.django, 10).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, None).unwrap();
+            contract.vote(accounts.django, 3, None).unwrap();
+            advance_blocks(1);
+
+            let short_of_quorum = contract.propose(Hash::from([0x01; 32]), None).unwrap();
+            let short_of_threshold = contract.propose(Hash::from([0x02; 32]), None).unwrap();
+            advance_blocks(1);
+            set_caller(accounts.charlie);
+            contract.cast_ballot(short_of_quorum, Ballot::Abstain).unwrap();
+            contract.cast_ballot(short_of_threshold, Ballot::Yes).unwrap();
+            set_caller(accounts.django);
+            contract.cast_ballot(short_of_threshold, Ballot::No).unwrap();
+
+            advance_blocks(3);
+            // 3 of 6 yes is below the 60% threshold.
+            for id in [short_of_quorum, short_of_threshold] {
+                assert_eq!(contract.proposal_state(id), Some(ProposalState::Defeated));
+                assert_eq!(contract.execute(id), Err(Error::ProposalDefeated));
+            }
+            assert_eq!(contract.execute(2), Err(Error::UnknownProposal));
+        }
+
+        #[ink::test]
+        fn ballots_weigh_decayed_reputation() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_governance_config(3, 4, 5_000).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 8, None).unwrap();
+
+            // Decay starts after Charlie's last write, which nothing
+            // touches again before the snapshot.
+            advance_blocks(1);
+            set_block_timestamp(0);
+            set_caller(accounts.alice);
+            contract.set_reputation_half_life(1_000).unwrap();
+            advance_blocks(2);
+            set_block_timestamp(2_000);
+
+            set_caller(accounts.charlie);
+            let id = contract.propose(Hash::from([0x01; 32]), None).unwrap();
+            advance_blocks(1);
+            contract.cast_ballot(id, Ballot::Yes).unwrap();
+            assert_eq!(contract.proposal(id).unwrap().yes, 2);
+
+            // Two is short of the quorum of four that the stale eight met.
+            advance_blocks(3);
+            assert_eq!(contract.proposal_state(id), Some(ProposalState::Defeated));
+        }
+
+        #[ink::test]
+        fn slash_is_recorded_and_reversible_within_appeal_window() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_slash_appeal_window(3).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 4, None).unwrap();
+            let reason_hash = Hash::from([0x05; 32]);
+            assert_eq!(contract.slash(accounts.charlie, 1, 0, reason_hash), Err(Error::MissingRole(Role::Moderator)));
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.slash(accounts.charlie, 0, 0, reason_hash), Err(Error::EmptySlash));
+            assert_eq!(contract.slash(accounts.charlie, -1, 0, reason_hash), Err(Error::EmptySlash));
+            assert_eq!(contract.slash(accounts.charlie, 6, 25, reason_hash), Ok(0));
+            let charlie = contract.get_voter(accounts.charlie).unwrap();
+            assert_eq!((charlie.reputation, charlie.available_votes), (-2, 0));
+
+            let block = ink::env::block_number::<ink::env::DefaultEnvironment>();
+            let record = contract.slash_record(accounts.charlie, 0).unwrap();
+            assert_eq!(record, SlashRecord {
+                amount: 6,
+                votes: 10,
+                reason_hash,
+                slashed_by: accounts.alice,
+                slashed_at: block,
+                appeal_until: block + 3,
+                appealed: false,
+                reversed: false,
+                categories: Vec::new(),
+                penalties: Vec::new(),
+            });
+            match recorded_events().last().unwrap() {
+                Event::Slashed(event) => {
+                    assert_eq!((event.account, event.index, event.amount, event.votes), (accounts.charlie, 0, 6, 10));
+                    assert_eq!(event.reason_hash, reason_hash);
+                }
+                _ => panic!("expected Slashed"),
+            }
+
+            advance_blocks(3);
+            set_caller(accounts.charlie);
+            assert_eq!(contract.appeal(1), Err(Error::UnknownSlash));
+            contract.appeal(0).unwrap();
+            assert_eq!(contract.appeal(0), Err(Error::AlreadyAppealed));
+            assert!(contract.slash_record(accounts.charlie, 0).unwrap().appealed);
+            set_caller(accounts.alice);
+            contract.pause(Some(Operation::Removal), None).unwrap();
+            assert_eq!(contract.reverse_slash(accounts.charlie, 0), Err(Error::OperationPaused(Operation::Removal)));
+            contract.unpause(Some(Operation::Removal)).unwrap();
+            contract.reverse_slash(accounts.charlie, 0).unwrap();
+            let charlie = contract.get_voter(accounts.charlie).unwrap();
+            assert_eq!((charlie.reputation, charlie.available_votes), (4, 10));
+            assert_eq!(contract.reverse_slash(accounts.charlie, 0), Err(Error::SlashAlreadyReversed));
+
+            contract.slash(accounts.charlie, 1, 0, reason_hash).unwrap();
+            advance_blocks(4);
+            assert_eq!(contract.reverse_slash(accounts.charlie, 1), Err(Error::AppealWindowClosed));
+            set_caller(accounts.charlie);
+            assert_eq!(contract.appeal(1), Err(Error::AppealWindowClosed));
+            set_caller(accounts.alice);
+            assert_eq!(contract.reverse_slash(accounts.charlie, 2), Err(Error::UnknownSlash));
+            assert_eq!(contract.slash_count(accounts.charlie), 2);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 3);
+        }
+
+        #[ink::test]
+        fn slashing_a_member_penalises_their_vouchers() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_vouching_config(1, 0, 2, 4).unwrap();
+            let applicant = AccountId::from([0x43; 32]);
+            set_caller(applicant);
+            contract.request_membership().unwrap();
+            set_caller(accounts.bob);
+            contract.vouch(applicant).unwrap();
+
+            set_caller(accounts.alice);
+            contract.slash(applicant, 1, 0, Hash::from([0x06; 32])).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, -2);
+            assert_eq!(contract.get_voter(applicant).unwrap().reputation, -1);
+            assert_eq!(contract.slash_record(applicant, 0).unwrap().penalties, vec![(accounts.bob, 2)]);
+
+            // Reversing the slash gives the vouchers their penalty back.
+            contract.reverse_slash(applicant, 0).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, 0);
+            assert_eq!(contract.get_voter(applicant).unwrap().reputation, 0);
+        }
+
+        /// Registers Django, Eve, Frank and one more voter next to Bob and
+        /// Charlie, and opens disputes with a bond of 100 and 3 jurors.
+        fn dispute_setup() -> OptionsAndFutures {
+            let accounts = accounts();
+            let mut contract = setup();
+            for voter in [accounts.django, accounts.eve, accounts.frank, AccountId::from([0x51; 32])] {
+                contract.add_voter(voter, 10).unwrap();
+            }
+            contract.set_dispute_config(100, 3, 5).unwrap();
+            contract
+        }
+
+        /// Has `disputant` dispute `vote_id` and draw its jurors once due.
+        fn open_and_draw(contract: &mut OptionsAndFutures, disputant: AccountId, vote_id: u64) -> u32 {
+            let secret = [vote_id as u8; 32];
+            pay(disputant, 100);
+            let id = contract.open_dispute(vote_id, contract.juror_seed_hash(disputant, secret)).unwrap();
+            advance_blocks(JUROR_DRAW_DELAY);
+            set_caller(disputant);
+            contract.draw_jurors(id, secret).unwrap();
+            id
+        }
+
+        #[ink::test]
+        fn upheld_dispute_reverses_vote_and_returns_bond() {
+            let accounts = accounts();
+            let mut contract = dispute_setup();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 4, None).unwrap();
+            contract.vote(accounts.charlie, 2, None).unwrap();
+            assert_eq!(contract.vote_entry(1).unwrap().effect, 2);
+
+            let secret = [0x0d; 32];
+            let commitment = contract.juror_seed_hash(accounts.django, secret);
+            pay(accounts.django, 99);
+            assert_eq!(contract.open_dispute(0, commitment), Err(Error::WrongDeposit));
+            pay(accounts.bob, 100);
+            assert_eq!(contract.open_dispute(0, commitment), Err(Error::OwnVote));
+            pay(accounts.django, 100);
+            assert_eq!(contract.open_dispute(2, commitment), Err(Error::UnknownVote));
+            let id = contract.open_dispute(0, commitment).unwrap();
+            assert_eq!(contract.open_dispute(0, commitment), Err(Error::VoteAlreadyDisputed));
+
+            // Jurors are drawn only once the delay has passed, with the
+            // committed secret.
+            assert!(contract.dispute(id).unwrap().jurors.is_empty());
+            assert_eq!(contract.draw_jurors(id, secret), Err(Error::DrawNotDue));
+            advance_blocks(JUROR_DRAW_DELAY);
+            assert_eq!(contract.draw_jurors(id, [0x0e; 32]), Err(Error::InvalidReveal));
+            contract.draw_jurors(id, secret).unwrap();
+            assert_eq!(contract.draw_jurors(id, secret), Err(Error::JurorsAlreadyDrawn));
+
+            let jurors = contract.dispute(id).unwrap().jurors;
+            assert_eq!(jurors.len(), 3);
+            for party in [accounts.bob, accounts.charlie, accounts.django] {
+                assert!(!jurors.contains(&party));
+            }
+            set_caller(accounts.bob);
+            assert_eq!(contract.rule(id, false), Err(Error::NotAJuror));
+            set_caller(jurors[0]);
+            contract.rule(id, true).unwrap();
+            assert_eq!(contract.rule(id, true), Err(Error::AlreadyRuled));
+            assert_eq!(contract.resolve_dispute(id), Err(Error::RulingOpen));
+            set_caller(jurors[1]);
+            contract.rule(id, true).unwrap();
+
+            let disputant_balance = balance_of(accounts.django);
+            contract.resolve_dispute(id).unwrap();
+            assert_eq!(contract.dispute(id).unwrap().status, DisputeStatus::Upheld);
+            assert_eq!(balance_of(accounts.django), disputant_balance + 100);
+            // Only the disputed vote of 4 is reversed.
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 2);
+            assert_eq!(contract.vote_between(accounts.bob, accounts.charlie).unwrap().net, 2);
+            assert_eq!(contract.resolve_dispute(id), Err(Error::DisputeResolved));
+            set_caller(jurors[2]);
+            assert_eq!(contract.rule(id, false), Err(Error::DisputeResolved));
+        }
+
+        #[ink::test]
+        fn rejected_dispute_pays_bond_to_defendant() {
+            let accounts = accounts();
+            let mut contract = dispute_setup();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 4, None).unwrap();
+
+            let id = open_and_draw(&mut contract, accounts.django, 0);
+            let jurors = contract.dispute(id).unwrap().jurors;
+            set_caller(jurors[0]);
+            contract.rule(id, true).unwrap();
+            set_caller(jurors[1]);
+            contract.rule(id, false).unwrap();
+
+            advance_blocks(5);
+            assert_eq!(contract.resolve_dispute(id), Err(Error::RulingOpen));
+            advance_blocks(1);
+            set_caller(jurors[2]);
+            assert_eq!(contract.rule(id, true), Err(Error::RulingClosed));
+
+            // A tie at the deadline rejects the dispute.
+            let defendant_balance = balance_of(accounts.bob);
+            contract.resolve_dispute(id).unwrap();
+            assert_eq!(contract.dispute(id).unwrap().status, DisputeStatus::Rejected);
+            assert_eq!(balance_of(accounts.bob), defendant_balance + 100);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 4);
+            match recorded_events().last().unwrap() {
+                Event::DisputeResolved(event) => assert_eq!(event.status, DisputeStatus::Rejected),
+                _ => panic!("expected DisputeResolved"),
+            }
+        }
+
+        #[ink::test]
+        fn disputes_need_enough_unrelated_jurors() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            assert_eq!(contract.set_dispute_config(100, MAX_JURORS + 1, 5), Err(Error::InvalidConfig));
+            contract.set_dispute_config(100, 1, 5).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 1, None).unwrap();
+
+            pay(accounts.django, 100);
+            assert_eq!(contract.open_dispute(0, Hash::from([0x0d; 32])), Err(Error::NotEnoughJurors));
+        }
+
+        #[ink::test]
+        fn undrawn_dispute_is_rejected_at_deadline() {
+            let accounts = accounts();
+            let mut contract = dispute_setup();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 4, None).unwrap();
+
+            let secret = [0x0d; 32];
+            pay(accounts.django, 100);
+            let id = contract.open_dispute(0, contract.juror_seed_hash(accounts.django, secret)).unwrap();
+            advance_blocks(JUROR_DRAW_DELAY + 5);
+            assert_eq!(contract.resolve_dispute(id), Err(Error::RulingOpen));
+            advance_blocks(1);
+            assert_eq!(contract.draw_jurors(id, secret), Err(Error::RulingClosed));
+            let defendant_balance = balance_of(accounts.bob);
+            contract.resolve_dispute(id).unwrap();
+            assert_eq!(contract.dispute(id).unwrap().status, DisputeStatus::Rejected);
+            assert_eq!(balance_of(accounts.bob), defendant_balance + 100);
+        }
+
+        #[ink::test]
+        fn jurors_vary_and_must_stay_registered() {
+            let accounts = accounts();
+            let mut contract = dispute_setup();
+            for seed in 0x52..0x55 {
+                contract.add_voter(AccountId::from([seed; 32]), 10).unwrap();
+            }
+            set_caller(accounts.bob);
+            for _ in 0..4 {
+                contract.vote(accounts.charlie, 1, None).unwrap();
+            }
+
+            let mut drawn: Vec<AccountId> = Vec::new();
+            for vote_id in 0..4 {
+                let id = open_and_draw(&mut contract, accounts.django, vote_id);
+                for juror in contract.dispute(id).unwrap().jurors {
+                    assert!(![accounts.bob, accounts.charlie, accounts.django].contains(&juror));
+                    if !drawn.contains(&juror) {
+                        drawn.push(juror);
+                    }
+                }
+            }
+            assert!(drawn.len() > 3);
+
+            let juror = contract.dispute(3).unwrap().jurors[0];
+            set_caller(accounts.alice);
+            contract.remove_voter(juror).unwrap();
+            set_caller(juror);
+            assert_eq!(contract.rule(3, true), Err(Error::UnregisteredVoter));
+        }
+
+        #[ink::test]
+        fn vote_weight_scales_reputation_effect() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 100).unwrap();
+            set_caller(accounts.django);
+            contract.vote(accounts.charlie, 9, None).unwrap();
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_vote_weight_config(Some(WeightCurve::Sqrt), 2_500, 2_000, 1_000), Err(Error::InvalidConfig));
+            assert_eq!(contract.set_vote_weight_config(Some(WeightCurve::Sqrt), 2_500, 0, 10_001), Err(Error::InvalidConfig));
+            contract.set_vote_weight_config(Some(WeightCurve::Sqrt), 2_500, 1_000, 10_000).unwrap();
+            assert_eq!(contract.effective_vote_weight(accounts.charlie), Some(7_500));
+            assert_eq!(contract.effective_vote_weight(accounts.bob), Some(1_000));
+            assert_eq!(contract.effective_vote_weight(accounts.eve), None);
+
+            // Charlie's weight of 0.75 turns 4 votes into 3 reputation, at
+            // the full cost of 4.
+            set_caller(accounts.charlie);
+            contract.vote(accounts.bob, 4, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, 3);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 6);
+            let record = contract.vote_between(accounts.charlie, accounts.bob).unwrap();
+            assert_eq!((record.net, record.effect), (4, 3));
+
+            // Django's reputation of 0 gets the floor of 0.1.
+            set_caller(accounts.django);
+            contract.vote(accounts.bob, -20, None).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, 1);
+
+            set_caller(accounts.alice);
+            contract.set_vote_weight_config(None, 0, 0, 0).unwrap();
+            assert_eq!(contract.vote_weight_config(), None);
+            assert_eq!(contract.effective_vote_weight(accounts.django), Some(BASIS_POINTS));
+
+            // Retracting a weighted vote refunds its full cost and takes back
+            // only the effect it had, whatever the weight is now.
+            set_caller(accounts.charlie);
+            contract.retract_vote(accounts.bob, 4).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, -2);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 10);
+        }
+
+        #[ink::test]
+        fn log2_vote_weight_is_clamped() {
+            let accounts = accounts();
+            let mut contract = setup();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 7, None).unwrap();
+            set_caller(accounts.alice);
+            contract.set_vote_weight_config(Some(WeightCurve::Log2), 3_000, 500, 8_000).unwrap();
+            set_caller(accounts.charlie);
+            contract.vote(accounts.bob, -1, None).unwrap();
+
+            // log2(7 + 1) = 3 units of 0.3, capped at 0.8.
+            assert_eq!(contract.effective_vote_weight(accounts.charlie), Some(8_000));
+            assert_eq!(contract.effective_vote_weight(accounts.bob), Some(500));
+            let config = contract.vote_weight_config().unwrap();
+            assert_eq!(config.weight(1), 3_000);
+            assert_eq!(config.weight(Reputation::MAX), 8_000);
+        }
+
+        #[ink::test]
+        fn commit_reveal_round_applies_only_revealed_votes() {
+            let accounts = accounts();
+            let mut contract = setup();
+            assert_eq!(contract.start_round(0, 3), Err(Error::InvalidConfig));
+            contract.start_round(3, 3).unwrap();
+            assert_eq!(contract.start_round(3, 3), Err(Error::RoundInProgress));
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.vote(accounts.charlie, 1, None), Err(Error::RoundInProgress));
+            let bob_salt = [0x0b; 32];
+            let commitment = contract.commitment_hash(accounts.bob, 0, accounts.charlie, 4, None, bob_salt);
+            assert_ne!(commitment, contract.commitment_hash(accounts.django, 0, accounts.charlie, 4, None, bob_salt));
+            assert_ne!(commitment, contract.commitment_hash(accounts.bob, 1, accounts.charlie, 4, None, bob_salt));
+            assert_eq!(contract.commit(commitment, 0), Err(Error::ZeroVotes));
+            assert_eq!(contract.commit(commitment, 11), Err(Error::InsufficientVotes));
+            contract.commit(commitment, 5).unwrap();
+            assert_eq!(contract.commit(commitment, 5), Err(Error::AlreadyCommitted));
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 5);
+            assert_eq!(contract.reveal(accounts.charlie, 4, None, bob_salt), Err(Error::NotRevealPhase));
+
+            set_caller(accounts.charlie);
+            let charlie_salt = [0x0c; 32];
+            contract.commit(contract.commitment_hash(accounts.charlie, 0, accounts.bob, -2, None, charlie_salt), 1).unwrap();
+            // Nothing reaches reputation before the reveal.
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 0);
+
+            advance_blocks(3);
+            set_caller(accounts.django);
+            assert_eq!(contract.commit(commitment, 1), Err(Error::NotCommitPhase));
+            assert_eq!(contract.reveal(accounts.charlie, 4, None, bob_salt), Err(Error::NoCommitment));
+            set_caller(accounts.bob);
+            assert_eq!(contract.reveal(accounts.charlie, 5, None, bob_salt), Err(Error::InvalidReveal));
+            contract.reveal(accounts.charlie, 4, None, bob_salt).unwrap();
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 4);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 6);
+            assert_eq!(contract.commitment_of(0, accounts.bob), None);
+
+            // Charlie's vote costs more than was locked, so it cannot be
+            // revealed and the locked vote is forfeited.
+            set_caller(accounts.charlie);
+            assert_eq!(contract.reveal(accounts.bob, -2, None, charlie_salt), Err(Error::ExceedsLockedVotes));
+            advance_blocks(3);
+            assert_eq!(contract.reveal(accounts.bob, -2, None, charlie_salt), Err(Error::NotRevealPhase));
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().available_votes, 9);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().reputation, 0);
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 1, None).unwrap();
+            set_caller(accounts.alice);
+            contract.start_round(1, 1).unwrap();
+            assert_eq!(contract.current_round().unwrap().id, 1);
+        }
+
+        #[ink::test]
+        fn epoch_refills_wait_for_locked_votes() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.set_epoch_config(10, 2, RefillMode::TopUp).unwrap();
+            contract.start_round(5, 20).unwrap();
+
+            set_caller(accounts.bob);
+            let salt = [0x0b; 32];
+            contract.commit(contract.commitment_hash(accounts.bob, 0, accounts.charlie, 4, None, salt), 5).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 7);
+
+            // The epoch turns while the votes are locked.
+            advance_blocks(10);
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 7);
+
+            // Revealing refunds what the vote did not cost, then the held
+            // back refill lands.
+            contract.reveal(accounts.charlie, 4, None, salt).unwrap();
+            assert_eq!(contract.get_voter(accounts.bob).unwrap().available_votes, 10);
+        }
+
+        #[ink::test]
+        fn category_votes_track_reputation_per_category() {
+            let accounts = accounts();
+            let mut contract = setup();
+            set_caller(accounts.bob);
+            assert_eq!(contract.add_category(Hash::from([0x01; 32])), Err(Error::MissingRole(Role::Admin)));
+            assert_eq!(contract.vote(accounts.charlie, 1, Some(0)), Err(Error::UnknownCategory));
+
+            set_caller(accounts.alice);
+            let settlement = contract.add_category(Hash::from([0x01; 32])).unwrap();
+            let pricing = contract.add_category(Hash::from([0x02; 32])).unwrap();
+            assert_eq!((settlement, pricing), (0, 1));
+            assert_eq!(contract.category(pricing), Some(Hash::from([0x02; 32])));
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 3, Some(settlement)).unwrap();
+            contract.vote(accounts.charlie, -1, Some(pricing)).unwrap();
+            contract.vote(accounts.charlie, 2, None).unwrap();
+
+            assert_eq!(contract.reputation_in(accounts.charlie, settlement), 3);
+            assert_eq!(contract.reputation_in(accounts.charlie, pricing), -1);
+            assert_eq!(contract.reputation_by_category(accounts.charlie), Some((vec![3, -1], 4)));
+            assert_eq!(contract.reputation_by_category(accounts.django), None);
+            match recorded_events().last().unwrap() {
+                Event::VoteCast(event) => assert_eq!(event.category, None),
+                _ => panic!("expected VoteCast"),
+            }
+
+            set_caller(accounts.alice);
+            contract.remove_voter(accounts.charlie).unwrap();
+            assert_eq!(contract.reputation_in(accounts.charlie, settlement), 0);
+        }
+
+        #[ink::test]
+        fn upheld_dispute_reverses_category_reputation() {
+            let accounts = accounts();
+            let mut contract = dispute_setup();
+            let category = contract.add_category(Hash::from([0x03; 32])).unwrap();
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 4, Some(category)).unwrap();
+
+            let id = open_and_draw(&mut contract, accounts.django, 0);
+            for juror in contract.dispute(id).unwrap().jurors.into_iter().take(2) {
+                set_caller(juror);
+                contract.rule(id, true).unwrap();
+            }
+            contract.resolve_dispute(id).unwrap();
+            assert_eq!(contract.reputation_in(accounts.charlie, category), 0);
+            assert_eq!(contract.get_voter(accounts.charlie).unwrap().reputation, 0);
+        }
+
+        #[ink::test]
+        fn category_reputation_follows_retraction_slash_and_decay() {
+            let accounts = accounts();
+            let mut contract = setup();
+            contract.add_voter(accounts.django, 10).unwrap();
+            let category = contract.add_category(Hash::from([0x05; 32])).unwrap();
+
+            set_caller(accounts.bob);
+            contract.vote(accounts.charlie, 8, Some(category)).unwrap();
+            contract.retract_vote(accounts.charlie, 4).unwrap();
+            assert_eq!(contract.reputation_in(accounts.charlie, category), 4);
+            contract.vote_many(vec![(accounts.django, 2, Some(category))]).unwrap();
+            contract.reallocate(accounts.charlie, accounts.django, 2, Some(category)).unwrap();
+            assert_eq!(contract.reputation_in(accounts.charlie, category), 2);
+            assert_eq!(contract.reputation_in(accounts.django, category), 4);
+
+            set_caller(accounts.alice);
+            let index = contract.slash(accounts.charlie, 1, 0, Hash::from([0x06; 32])).unwrap();
+            assert_eq!(contract.reputation_in(accounts.charlie, category), 1);
+            contract.reverse_slash(accounts.charlie, index).unwrap();
+            assert_eq!(contract.reputation_in(accounts.charlie, category), 2);
+
+            contract.set_reputation_half_life(1_000).unwrap();
+            set_block_timestamp(1_000);
+            assert_eq!(contract.reputation_in(accounts.charlie, category), 1);
+            set_caller(accounts.bob);
+            contract.retract_vote(accounts.charlie, 2).unwrap();
+            assert_eq!(contract.reputation_by_category(accounts.charlie), Some((vec![0], 0)));
+        }
+
+        #[ink::test]
+        fn categories_are_capped() {
+            let mut contract = setup();
+            for _ in 0..MAX_CATEGORIES {
+                contract.add_category(Hash::from([0x04; 32])).unwrap();
+            }
+            assert_eq!(contract.add_category(Hash::from([0x04; 32])), Err(Error::TooManyCategories));
+        }
+    }
+}